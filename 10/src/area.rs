@@ -0,0 +1,46 @@
+use std::collections::HashSet;
+
+use crate::{Coord, Direction, Grid, Pipe};
+use crate::pipe_loop::PipeLoop;
+
+// Uses the shoelace formula to get the area of the polygon through the centres of the loop
+// tiles, and then Pick's theorem (A = I + B/2 - 1) to get the number of interior tiles I
+pub fn count_inside_shoelace(pipe_loop: &PipeLoop) -> usize {
+    let tiles: &Vec<Coord> = &pipe_loop.tiles;
+    let double_area: i64 =
+        tiles.iter()
+            .zip(tiles.iter().cycle().skip(1))
+            .map(|(Coord(row1, col1), Coord(row2, col2))|
+                *col1 as i64 * *row2 as i64 - *col2 as i64 * *row1 as i64)
+            .sum::<i64>()
+            .abs();
+    let boundary = tiles.len() as i64;
+    ((double_area - boundary) / 2 + 1) as usize
+}
+
+// Sweeps every row from left to right, counting how many times the loop is crossed. Only pipes
+// that connect upwards count as a crossing, so that a run like L--7 is a single crossing and a
+// run like L--J is none
+pub fn find_coords_inside_scanline(grid: &Grid, pipe_loop: &PipeLoop) -> HashSet<Coord> {
+    let mut on_loop: Vec<bool> = vec![false; grid.rows * grid.cols];
+    pipe_loop.tiles.iter().for_each(|&coord| on_loop[grid.to_index(coord)] = true);
+
+    let mut result: HashSet<Coord> = HashSet::new();
+    for row in 0..grid.rows as i32 {
+        let mut inside = false;
+        for col in 0..grid.cols as i32 {
+            let coord = Coord(row, col);
+            if !on_loop[grid.to_index(coord)] {
+                if inside {
+                    result.insert(coord);
+                }
+                continue;
+            }
+            let pipe: Pipe = pipe_loop.pipe_at(grid, coord).unwrap();
+            if pipe.can_pass_through(coord.plus(Direction::Up)) {
+                inside = !inside;
+            }
+        }
+    }
+    result
+}
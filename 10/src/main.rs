@@ -3,6 +3,14 @@ use std::collections::LinkedList;
 use std::env;
 use std::fs;
 
+use area::{count_inside_shoelace, find_coords_inside_scanline};
+use pipe_loop::{PipeLoop, trace_pipe_loop};
+use render::{render_svg, render_terminal};
+
+mod area;
+mod pipe_loop;
+mod render;
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let contents: String =
@@ -22,20 +30,56 @@ fn main() {
             data: parsed_lines.into_iter().flatten().collect()
         };
     let start_pos: Coord = find_start_pos(&grid);
+    let pipe_loop: PipeLoop = match trace_pipe_loop(&grid, start_pos) {
+        Ok(pipe_loop) => pipe_loop,
+        Err(error) => {
+            println!("Could not trace the pipe loop: {error}");
+            return;
+        }
+    };
+
+    if has_flag(&args, "--render") || has_flag(&args, "--svg") {
+        let inside: HashSet<Coord> = find_coords_inside_scanline(&grid, &pipe_loop);
+        if has_flag(&args, "--render") {
+            print!("{}", render_terminal(&grid, &pipe_loop, &inside));
+        }
+        if let Some(path) = parse_flag::<String>(&args, "--svg") {
+            fs::write(&path, render_svg(&grid, &pipe_loop, &inside)).expect("should be able to write the SVG");
+        }
+    }
 
     match version {
         1 => {
-            let result = find_pipe_loop_length(&grid, start_pos) / 2;
+            let result = pipe_loop.tiles.len() / 2;
             println!("Result is {result}");
         },
         2 => {
-            let result = find_coords_inside_pipe_loop(&grid, start_pos).len();
+            let method: String = parse_flag(&args, "--method").unwrap_or(String::from("flood"));
+            let result = match method.as_str() {
+                "flood" => find_coords_inside_pipe_loop(&grid, &pipe_loop).len(),
+                "shoelace" => count_inside_shoelace(&pipe_loop),
+                "scanline" => find_coords_inside_scanline(&grid, &pipe_loop).len(),
+                "all" => match cross_validate(&grid, &pipe_loop) {
+                    Some(result) => result,
+                    None => return
+                },
+                _ => panic!("Unknown method {method}")
+            };
             println!("Result is {result}");
         },
         _ => panic!("Unknown version {version}")
     };
 }
 
+fn parse_flag<T: std::str::FromStr>(args: &[String], name: &str) -> Option<T> {
+    args.iter()
+        .position(|arg| arg == name)
+        .map(|index| args.get(index + 1).expect("Flag should have a value"))
+        .map(|value| value.parse().unwrap_or_else(|_| panic!("Invalid value {value} for {name}")))
+}
+
+fn has_flag(args: &[String], name: &str) -> bool { args.iter().any(|arg| arg == name) }
+
 fn parse_line(line: &str, row: i32) -> Vec<Option<Pipe>> {
     line.chars()
         .enumerate()
@@ -67,59 +111,11 @@ fn find_start_pos(grid: &Grid) -> Coord {
     panic!("Could not find start position");
 }
 
-fn find_pipe_loop_length(grid: &Grid, start_pos: Coord) -> u32 {
-    find_pipe_loop_directions(grid, start_pos).len() as u32
-}
-
-fn find_pipe_loop_directions(grid: &Grid, start_pos: Coord) -> Vec<Direction> {
-    for neighbour in get_connected_neighbours(grid, start_pos) {
-        match find_pipe_loop_directions_rec(grid, neighbour, start_pos, start_pos) {
-            None => continue,
-            Some(directions) => return directions
-        }
-    }
-    panic!("There are no loops!");
-}
-
-fn find_pipe_loop_directions_rec(
-    grid: &Grid,
-    current: Coord,
-    previous: Coord,
-    target: Coord,
-) -> Option<Vec<Direction>> {
-    if current == target {
-        return Some(vec![previous.to(current)]);
-    }
-    match grid.get(current) {
-        None => None,
-        Some(pipe) => {
-            if !pipe.can_pass_through(previous) {
-                return None;
-            }
-            find_pipe_loop_directions_rec(grid, pipe.pass_through(previous), current, target)
-                .map(|tail| {
-                    let mut head = vec![previous.to(current)];
-                    head.extend(tail);
-                    head
-                })
-        }
-    }
-}
-
-fn get_connected_neighbours(grid: &Grid, start_pos: Coord) -> Vec<Coord> {
-    start_pos.neighbours().into_iter()
-        .filter(|&neighbour| match grid.get(neighbour) {
-            None => false,
-            Some(pipe) => pipe.can_pass_through(start_pos)
-        })
-        .collect()
-}
-
-fn find_coords_inside_pipe_loop(grid: &Grid, start_pos: Coord) -> HashSet<Coord> {
+fn find_coords_inside_pipe_loop(grid: &Grid, pipe_loop: &PipeLoop) -> HashSet<Coord> {
     let rows_micro: i32 = 2 * grid.rows as i32;
     let cols_micro: i32 = 2 * grid.cols as i32;
 
-    let forbidden: HashSet<Coord> = find_pipe_loop_micro_coords(grid, start_pos);
+    let forbidden: HashSet<Coord> = find_pipe_loop_micro_coords(pipe_loop);
     let mut visited: HashSet<Coord> = HashSet::new();
     let mut queue: LinkedList<Coord> = LinkedList::new();
 
@@ -128,7 +124,7 @@ fn find_coords_inside_pipe_loop(grid: &Grid, start_pos: Coord) -> HashSet<Coord>
         .filter(|coord_micro| !forbidden.contains(coord_micro))
         .for_each(|coord_micro| queue.push_back(coord_micro));
     (0..rows_micro)
-        .flat_map(|col_micro| [Coord(0, col_micro), Coord(rows_micro - 1, col_micro)])
+        .flat_map(|row_micro| [Coord(row_micro, 0), Coord(row_micro, cols_micro - 1)])
         .filter(|coord_micro| !forbidden.contains(coord_micro))
         .for_each(|coord_micro| queue.push_back(coord_micro));
 
@@ -160,17 +156,34 @@ fn find_coords_inside_pipe_loop(grid: &Grid, start_pos: Coord) -> HashSet<Coord>
         .collect::<HashSet<Coord>>()
 }
 
-fn find_pipe_loop_micro_coords(grid: &Grid, start_pos_macro: Coord) -> HashSet<Coord> {
-    let directions_macro: Vec<Direction> = find_pipe_loop_directions(grid, start_pos_macro);
-    let start_pos_micro: Coord = Coord(start_pos_macro.0 * 2, start_pos_macro.1 * 2);
-    let mut result: HashSet<Coord> = HashSet::from([start_pos_micro]);
+// Runs every method for part 2 and reports where they disagree. Returns the count if they all agree
+fn cross_validate(grid: &Grid, pipe_loop: &PipeLoop) -> Option<usize> {
+    let flood: HashSet<Coord> = find_coords_inside_pipe_loop(grid, pipe_loop);
+    let scanline: HashSet<Coord> = find_coords_inside_scanline(grid, pipe_loop);
+    let shoelace: usize = count_inside_shoelace(pipe_loop);
+
+    let mut agree = true;
+    for coord in flood.symmetric_difference(&scanline) {
+        let found_by = if flood.contains(coord) { "flood" } else { "scanline" };
+        println!("Tile {:?} is only inside according to {found_by}", coord);
+        agree = false;
+    }
+    if flood.len() != shoelace || scanline.len() != shoelace {
+        println!("Counts disagree: flood={}, scanline={}, shoelace={}", flood.len(), scanline.len(), shoelace);
+        agree = false;
+    }
+    if agree { Some(shoelace) } else { None }
+}
+
+fn find_pipe_loop_micro_coords(pipe_loop: &PipeLoop) -> HashSet<Coord> {
+    let tiles_macro: &Vec<Coord> = &pipe_loop.tiles;
+    let mut result: HashSet<Coord> = HashSet::new();
 
-    let mut current = start_pos_micro;
-    for direction in directions_macro {
-        for _ in 0..2 {
-            current = current.plus(direction);
-            result.insert(current);
-        }
+    for (index, &current_macro) in tiles_macro.iter().enumerate() {
+        let next_macro = tiles_macro[(index + 1) % tiles_macro.len()];
+        let current_micro = Coord(current_macro.0 * 2, current_macro.1 * 2);
+        result.insert(current_micro);
+        result.insert(current_micro.plus(current_macro.to(next_macro)));
     }
 
     result
@@ -206,26 +219,6 @@ impl Grid {
     // }
 
     fn to_index(&self, coord: Coord) -> usize { self.cols * (coord.0 as usize) + (coord.1 as usize)}
-
-    // fn to_string(&self) -> String {
-    //     let mut result = String::new();
-    //     for row in 0..self.rows {
-    //         for col in 0..self.cols {
-    //             match self.get(Coord(row, col)) {
-    //                 None => result.push('.'),
-    //                 Some(Pipe::StarPos) => result.push('S'),
-    //                 Some(Pipe::Vertical(_)) => result.push('|'),
-    //                 Some(Pipe::Horizontal(_)) => result.push('-'),
-    //                 Some(Pipe::NECurve(_)) => result.push('L'),
-    //                 Some(Pipe::NWCurve(_)) => result.push('J'),
-    //                 Some(Pipe::SWCurve(_)) => result.push('7'),
-    //                 Some(Pipe::SECurve(_)) => result.push('F')
-    //             }
-    //         }
-    //         result.push('\n');
-    //     }
-    //     result
-    // }
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -311,11 +304,34 @@ impl Pipe {
             Pipe::StarPos => false
         }
     }
+
+    fn from_directions(coord: Coord, first: Direction, second: Direction) -> Pipe {
+        let has = |direction: Direction| first == direction || second == direction;
+        if has(Direction::Up) && has(Direction::Down) {
+            Pipe::Vertical(coord)
+        } else if has(Direction::Left) && has(Direction::Right) {
+            Pipe::Horizontal(coord)
+        } else if has(Direction::Up) && has(Direction::Right) {
+            Pipe::NECurve(coord)
+        } else if has(Direction::Up) && has(Direction::Left) {
+            Pipe::NWCurve(coord)
+        } else if has(Direction::Down) && has(Direction::Left) {
+            Pipe::SWCurve(coord)
+        } else if has(Direction::Down) && has(Direction::Right) {
+            Pipe::SECurve(coord)
+        } else {
+            panic!("Directions {:?} and {:?} don't form a pipe", first, second)
+        }
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 enum Direction { Up, Down, Left, Right }
 
+impl Direction {
+    const ALL: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+}
+
 #[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
 struct Coord(i32, i32);
 
@@ -0,0 +1,83 @@
+use std::fmt;
+
+use crate::{Coord, Direction, Grid, Pipe};
+
+pub struct PipeLoop {
+    // The pipe that is hidden under the start position
+    pub start_pipe: Pipe,
+    // Every tile of the loop in traversal order, starting at the start position
+    pub tiles: Vec<Coord>
+}
+
+impl PipeLoop {
+    // Same as the grid, except that the start position yields the pipe hidden under it
+    pub fn pipe_at(&self, grid: &Grid, coord: Coord) -> Option<Pipe> {
+        if coord == self.tiles[0] { Some(self.start_pipe) } else { grid.get(coord) }
+    }
+}
+
+#[derive(Debug)]
+pub enum LoopError {
+    NoLoop,
+    // More than one loop passes through the start position, one for each candidate pipe
+    Ambiguous(Vec<Pipe>)
+}
+
+impl fmt::Display for LoopError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoopError::NoLoop => write!(f, "no loop passes through the start position"),
+            LoopError::Ambiguous(candidates) =>
+                write!(f, "the start position could be any of {} pipes: {:?}", candidates.len(), candidates)
+        }
+    }
+}
+
+pub fn trace_pipe_loop(grid: &Grid, start_pos: Coord) -> Result<PipeLoop, LoopError> {
+    let connected: Vec<Direction> =
+        Direction::ALL.into_iter()
+            .filter(|&direction| match grid.get(start_pos.plus(direction)) {
+                None | Some(Pipe::StarPos) => false,
+                Some(pipe) => pipe.can_pass_through(start_pos)
+            })
+            .collect();
+
+    let mut loops: Vec<PipeLoop> = Vec::new();
+    for (index, &first) in connected.iter().enumerate() {
+        for &second in connected.iter().skip(index + 1) {
+            let start_pipe = Pipe::from_directions(start_pos, first, second);
+            if let Some(tiles) = trace_from(grid, start_pos, first, second) {
+                loops.push(PipeLoop { start_pipe, tiles });
+            }
+        }
+    }
+
+    match loops.len() {
+        0 => Err(LoopError::NoLoop),
+        1 => Ok(loops.pop().unwrap()),
+        _ => Err(LoopError::Ambiguous(loops.iter().map(|pipe_loop| pipe_loop.start_pipe).collect()))
+    }
+}
+
+// Leaves the start position through `exit` and follows the pipes until it either dead-ends or
+// comes back to the start position through `entry`
+fn trace_from(grid: &Grid, start_pos: Coord, exit: Direction, entry: Direction) -> Option<Vec<Coord>> {
+    let mut tiles: Vec<Coord> = vec![start_pos];
+    let mut previous: Coord = start_pos;
+    let mut current: Coord = start_pos.plus(exit);
+    while current != start_pos {
+        let pipe = match grid.get(current) {
+            None | Some(Pipe::StarPos) => return None,
+            Some(pipe) => pipe
+        };
+        if !pipe.can_pass_through(previous) {
+            return None;
+        }
+        tiles.push(current);
+        (previous, current) = (current, pipe.pass_through(previous));
+    }
+    if start_pos.to(previous) != entry {
+        return None;
+    }
+    Some(tiles)
+}
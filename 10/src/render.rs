@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+
+use crate::{Coord, Direction, Grid, Pipe};
+use crate::pipe_loop::PipeLoop;
+
+const LOOP_COLOUR: &str = "\x1b[1;33m";
+const INSIDE_COLOUR: &str = "\x1b[32m";
+const JUNK_COLOUR: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+const SVG_TILE_SIZE: i32 = 10;
+
+#[derive(PartialEq, Eq, Copy, Clone)]
+enum TileKind { Loop, Inside, Junk, Ground }
+
+pub fn render_terminal(grid: &Grid, pipe_loop: &PipeLoop, inside: &HashSet<Coord>) -> String {
+    let loop_tiles: HashSet<Coord> = pipe_loop.tiles.iter().copied().collect();
+    let mut result = String::new();
+    for row in 0..grid.rows as i32 {
+        for col in 0..grid.cols as i32 {
+            let coord = Coord(row, col);
+            let pipe: Option<Pipe> = pipe_loop.pipe_at(grid, coord);
+            let character = pipe.map(box_character).unwrap_or('.');
+            match classify(coord, pipe, &loop_tiles, inside) {
+                TileKind::Loop => result.push_str(&format!("{LOOP_COLOUR}{character}{RESET}")),
+                TileKind::Inside => result.push_str(&format!("{INSIDE_COLOUR}{character}{RESET}")),
+                TileKind::Junk => result.push_str(&format!("{JUNK_COLOUR}{character}{RESET}")),
+                TileKind::Ground => result.push(' ')
+            }
+        }
+        result.push('\n');
+    }
+    result
+}
+
+pub fn render_svg(grid: &Grid, pipe_loop: &PipeLoop, inside: &HashSet<Coord>) -> String {
+    let loop_tiles: HashSet<Coord> = pipe_loop.tiles.iter().copied().collect();
+    let width = grid.cols as i32 * SVG_TILE_SIZE;
+    let height = grid.rows as i32 * SVG_TILE_SIZE;
+    let mut result = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n");
+    result.push_str(&format!("<rect width=\"{width}\" height=\"{height}\" fill=\"black\"/>\n"));
+    for row in 0..grid.rows as i32 {
+        for col in 0..grid.cols as i32 {
+            let coord = Coord(row, col);
+            let pipe: Option<Pipe> = pipe_loop.pipe_at(grid, coord);
+            let (x, y) = (col * SVG_TILE_SIZE, row * SVG_TILE_SIZE);
+            let kind = classify(coord, pipe, &loop_tiles, inside);
+            if kind == TileKind::Inside {
+                result.push_str(&format!(
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{SVG_TILE_SIZE}\" height=\"{SVG_TILE_SIZE}\" fill=\"darkgreen\"/>\n"));
+            }
+            let (colour, stroke_width) = match kind {
+                TileKind::Loop => ("gold", 3),
+                TileKind::Inside => ("lightgreen", 1),
+                TileKind::Junk => ("dimgray", 1),
+                TileKind::Ground => continue
+            };
+            let pipe: Pipe = match pipe {
+                None => continue,
+                Some(pipe) => pipe
+            };
+            let centre = (x + SVG_TILE_SIZE / 2, y + SVG_TILE_SIZE / 2);
+            for direction in connections(coord, pipe) {
+                let (dx, dy) = match direction {
+                    Direction::Up => (0, -1),
+                    Direction::Down => (0, 1),
+                    Direction::Left => (-1, 0),
+                    Direction::Right => (1, 0)
+                };
+                let end = (centre.0 + dx * SVG_TILE_SIZE / 2, centre.1 + dy * SVG_TILE_SIZE / 2);
+                result.push_str(&format!(
+                    "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{colour}\" stroke-width=\"{stroke_width}\" stroke-linecap=\"round\"/>\n",
+                    centre.0, centre.1, end.0, end.1));
+            }
+        }
+    }
+    result.push_str("</svg>\n");
+    result
+}
+
+fn classify(coord: Coord, pipe: Option<Pipe>, loop_tiles: &HashSet<Coord>, inside: &HashSet<Coord>) -> TileKind {
+    if loop_tiles.contains(&coord) {
+        TileKind::Loop
+    } else if inside.contains(&coord) {
+        TileKind::Inside
+    } else if pipe.is_some() {
+        TileKind::Junk
+    } else {
+        TileKind::Ground
+    }
+}
+
+fn connections(coord: Coord, pipe: Pipe) -> Vec<Direction> {
+    Direction::ALL.into_iter()
+        .filter(|&direction| pipe.can_pass_through(coord.plus(direction)))
+        .collect()
+}
+
+fn box_character(pipe: Pipe) -> char {
+    match pipe {
+        Pipe::Vertical(_) => '│',
+        Pipe::Horizontal(_) => '─',
+        Pipe::NECurve(_) => '└',
+        Pipe::NWCurve(_) => '┘',
+        Pipe::SWCurve(_) => '┐',
+        Pipe::SECurve(_) => '┌',
+        Pipe::StarPos => 'S'
+    }
+}
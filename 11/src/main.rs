@@ -1,7 +1,10 @@
-use std::collections::HashSet;
 use std::env;
 use std::fs;
 
+use universe::ExpandedUniverse;
+
+mod universe;
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let contents: String =
@@ -9,7 +12,7 @@ fn main() {
             .expect("should be able to read the file");
     let version: u64 = args[2].parse().expect("Should have a problem version");
 
-    let lines: Vec<&str> = contents.trim().split("\n").collect();
+    let lines: Vec<&str> = contents.trim().split('\n').collect();
     let max_y: i32 = lines.len() as i32 - 1;
     let galaxies: Vec<Point> =
         lines.iter()
@@ -17,20 +20,38 @@ fn main() {
             .map(|(row, line)| (max_y - row as i32, line))
             .flat_map(|(y, line)| parse_line(line, y))
             .collect();
-    let empty_space: HashSet<Line> = find_empty_lines(&galaxies);
-    let expansion_factor: u32 = match version {
+    let default_factor: u64 = match version {
         1 => 2,
         2 => 1000000,
         _ => panic!("Unknown version {version}")
     };
-    let result: u64 =
-        (0..galaxies.len())
-            .flat_map(|i| ((i+1)..galaxies.len()).map(move |j| (i, j)))
-            .map(|(i, j)| find_shortest_path(galaxies[i], galaxies[j], &empty_space, expansion_factor))
-            .sum();
-    println!("Result is {result}");
+    let factor_x: u64 = parse_flag(&args, "--expand-x").unwrap_or(default_factor);
+    let factor_y: u64 = parse_flag(&args, "--expand-y").unwrap_or(default_factor);
+    let universe =
+        ExpandedUniverse::new(&galaxies, factor_x, factor_y)
+            .unwrap_or_else(|error| panic!("Invalid expansion factors: {error}"));
+
+    if has_flag(&args, "--farthest") {
+        print_pair("Farthest", &galaxies, universe.farthest_pair());
+    }
+    if has_flag(&args, "--nearest") {
+        print_pair("Nearest", &galaxies, universe.nearest_pair());
+    }
+    match universe.sum_of_distances() {
+        Some(result) => println!("Result is {result}"),
+        None => eprintln!("Result does not fit in u64")
+    }
 }
 
+fn parse_flag<T: std::str::FromStr>(args: &[String], name: &str) -> Option<T> {
+    args.iter()
+        .position(|arg| arg == name)
+        .map(|index| args.get(index + 1).expect("Flag should have a value"))
+        .map(|value| value.parse().unwrap_or_else(|_| panic!("Invalid value {value} for {name}")))
+}
+
+fn has_flag(args: &[String], name: &str) -> bool { args.iter().any(|arg| arg == name) }
+
 fn parse_line(line: &str, y: i32) -> Vec<Point> {
     line.chars()
         .enumerate()
@@ -38,63 +59,14 @@ fn parse_line(line: &str, y: i32) -> Vec<Point> {
         .collect()
 }
 
-fn find_empty_lines(points: &Vec<Point>) -> HashSet<Line> {
-    if points.is_empty() {
-        return HashSet::new();
+fn print_pair(label: &str, galaxies: &[Point], pair: Option<(usize, usize, u64)>) {
+    match pair {
+        None => println!("{label} pair does not exist"),
+        Some((i, j, distance)) =>
+            println!("{label} pair is galaxy {} at {:?} and galaxy {} at {:?} with a path of length {distance}",
+                i + 1, galaxies[i], j + 1, galaxies[j])
     }
-    let seen_x_coords: HashSet<i32> = points.iter().map(|p| p.0).collect();
-    let seen_y_coords: HashSet<i32> = points.iter().map(|p| p.1).collect();
-
-    let min_x: i32 = seen_x_coords.iter().min().map(|x| *x).unwrap_or(0);
-    let max_x: i32 = seen_x_coords.iter().max().map(|x| *x).unwrap_or(0);
-    let vertical_lines =
-        (min_x..max_x)
-            .filter(|x| !seen_x_coords.contains(x))
-            .map(|x| Line(Point(x, 0), Vector(1, 0)));
-
-    let min_y: i32 = seen_y_coords.iter().min().map(|y| *y).unwrap_or(0);
-    let max_y: i32 = seen_y_coords.iter().max().map(|y| *y).unwrap_or(0);
-    let horizontal_lines =
-        (min_y..max_y)
-            .filter(|y| !seen_y_coords.contains(y))
-            .map(|y| Line(Point(0, y), Vector(0, 1)));
-
-    vertical_lines.chain(horizontal_lines).collect()
-}
-
-fn find_shortest_path(galaxy1: Point, galaxy2: Point, empty_space: &HashSet<Line>, expansion_factor: u32) -> u64 {
-    let intersections: u64 =
-        empty_space.iter()
-            .filter(|line| line.are_on_different_sides(galaxy1, galaxy2))
-            .count() as u64;
-    galaxy1.distance_l1(galaxy2) as u64 + intersections * (expansion_factor as u64 - 1)
 }
 
-#[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
+#[derive(Debug, Copy, Clone)]
 struct Point(i32, i32);
-
-impl Point {
-    fn minus(&self, p: Point) -> Vector { Vector(self.0 - p.0, self.1 - p.1) }
-    fn distance_l1(&self, other: Point) -> u32 { self.0.abs_diff(other.0) + self.1.abs_diff(other.1) }
-}
-
-#[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
-struct Vector(i32, i32);
-
-impl Vector {
-    fn dot_product(&self, other: Vector) -> i32 { (self.0 * other.0) + (self.1 * other.1) }
-}
-
-#[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
-struct Line(Point, Vector);
-
-impl Line {
-    fn are_on_different_sides(&self, a: Point, b: Point) -> bool {
-        let dot_product_a: i32 = a.minus(self.0).dot_product(self.1);
-        let dot_product_b: i32 = b.minus(self.0).dot_product(self.1);
-        if dot_product_a == 0 || dot_product_b == 0 {
-            return false;
-        }
-        dot_product_a.signum() != dot_product_b.signum()
-    }
-}
\ No newline at end of file
@@ -0,0 +1,189 @@
+use std::collections::BTreeSet;
+
+use crate::Point;
+
+// Galaxy positions after every empty row and column has been resized. Each empty line takes up
+// `factor` lines, so a factor of 1 leaves the universe unchanged and 0 removes empty space.
+// Coordinates are kept below a quarter of i64::MAX, so that sums and differences of two of them
+// always fit
+pub struct ExpandedUniverse {
+    pub galaxies: Vec<(i64, i64)>
+}
+
+impl ExpandedUniverse {
+    pub fn new(galaxies: &[Point], factor_x: u64, factor_y: u64) -> Result<ExpandedUniverse, String> {
+        let xs: Vec<i64> = expand_axis(&galaxies.iter().map(|p| p.0).collect::<Vec<i32>>(), factor_x)?;
+        let ys: Vec<i64> = expand_axis(&galaxies.iter().map(|p| p.1).collect::<Vec<i32>>(), factor_y)?;
+        Ok(ExpandedUniverse { galaxies: xs.into_iter().zip(ys).collect() })
+    }
+
+    // Since the L1 distance is separable, the sum over all pairs is the sum of the pairwise
+    // distances along each axis, and each of those only needs the sorted coordinates
+    pub fn sum_of_distances(&self) -> Option<u64> {
+        let xs: Vec<i64> = self.galaxies.iter().map(|g| g.0).collect();
+        let ys: Vec<i64> = self.galaxies.iter().map(|g| g.1).collect();
+        sum_of_pairwise_differences(xs).checked_add(sum_of_pairwise_differences(ys))?.try_into().ok()
+    }
+
+    // The L1 distance is the largest of |(x1 + y1) - (x2 + y2)| and |(x1 - y1) - (x2 - y2)|, so
+    // the farthest pair is made up of the extremes of one of those two projections
+    pub fn farthest_pair(&self) -> Option<(usize, usize, u64)> {
+        if self.galaxies.len() < 2 {
+            return None;
+        }
+        [1, -1].iter()
+            .map(|sign| {
+                let indexed = || self.galaxies.iter().enumerate().map(|(i, g)| (g.0 + sign * g.1, i));
+                let (_, min_index) = indexed().min().unwrap();
+                let (_, max_index) = indexed().max().unwrap();
+                (min_index.min(max_index), min_index.max(max_index), self.distance(min_index, max_index))
+            })
+            .max_by_key(|(_, _, distance)| *distance)
+    }
+
+    // Sweeps the galaxies from left to right, only keeping those whose x is within the best
+    // distance so far, ordered by y so the ones that can still be closer are a contiguous range
+    pub fn nearest_pair(&self) -> Option<(usize, usize, u64)> {
+        let mut order: Vec<usize> = (0..self.galaxies.len()).collect();
+        order.sort_by_key(|&i| self.galaxies[i]);
+
+        let mut best: Option<(usize, usize, u64)> = None;
+        let mut active: BTreeSet<(i64, usize)> = BTreeSet::new();
+        let mut oldest: usize = 0;
+        for &current in order.iter() {
+            let (x, y) = self.galaxies[current];
+            let best_distance: i64 = best.map(|(_, _, distance)| distance as i64).unwrap_or(i64::MAX);
+            while oldest < order.len() && x - self.galaxies[order[oldest]].0 > best_distance {
+                let old_y = self.galaxies[order[oldest]].1;
+                active.remove(&(old_y, order[oldest]));
+                oldest += 1;
+            }
+            let low = y.saturating_sub(best_distance);
+            let high = y.saturating_add(best_distance);
+            for &(_, other) in active.range((low, 0)..=(high, usize::MAX)) {
+                let distance = self.distance(current, other);
+                if best.is_none_or(|(_, _, best_distance)| distance < best_distance) {
+                    best = Some((current.min(other), current.max(other), distance));
+                }
+            }
+            active.insert((y, current));
+        }
+        best
+    }
+
+    fn distance(&self, i: usize, j: usize) -> u64 {
+        let (x1, y1) = self.galaxies[i];
+        let (x2, y2) = self.galaxies[j];
+        x1.abs_diff(x2) + y1.abs_diff(y2)
+    }
+}
+
+const MAX_COORDINATE: i128 = i64::MAX as i128 / 4;
+
+fn expand_axis(coords: &[i32], factor: u64) -> Result<Vec<i64>, String> {
+    let min: i32 = match coords.iter().min() {
+        None => return Ok(Vec::new()),
+        Some(min) => *min
+    };
+    let max: i32 = *coords.iter().max().unwrap();
+    let mut occupied: Vec<bool> = vec![false; (max - min + 1) as usize];
+    coords.iter().for_each(|c| occupied[(c - min) as usize] = true);
+
+    // empty_before[i] is how many empty lines there are between min and min + i
+    let mut empty_before: Vec<i128> = Vec::with_capacity(occupied.len());
+    let mut empty_count: i128 = 0;
+    for is_occupied in occupied {
+        empty_before.push(empty_count);
+        if !is_occupied {
+            empty_count += 1;
+        }
+    }
+
+    // Neither the growth nor the count of empty lines goes past 2^64, so their product fits
+    let growth: i128 = factor as i128 - 1;
+    coords.iter()
+        .map(|c| {
+            let expanded: i128 = *c as i128 + growth * empty_before[(c - min) as usize];
+            if expanded.abs() > MAX_COORDINATE {
+                return Err(format!("Expanding by {factor} puts galaxies beyond {MAX_COORDINATE}"));
+            }
+            Ok(expanded as i64)
+        })
+        .collect()
+}
+
+// Values are below 2^61 and there are far fewer than 2^64 of them, so nothing overflows in i128
+fn sum_of_pairwise_differences(mut values: Vec<i64>) -> i128 {
+    values.sort_unstable();
+    let mut prefix_sum: i128 = 0;
+    let mut total: i128 = 0;
+    for (index, &value) in values.iter().enumerate() {
+        total += value as i128 * index as i128 - prefix_sum;
+        prefix_sum += value as i128;
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn test_universe(factor: u64) -> ExpandedUniverse {
+        let contents: String = fs::read_to_string("test_input.txt").unwrap();
+        let lines: Vec<&str> = contents.trim().split('\n').collect();
+        let galaxies: Vec<Point> =
+            lines.iter()
+                .enumerate()
+                .flat_map(|(row, line)| crate::parse_line(line, (lines.len() - 1 - row) as i32))
+                .collect();
+        ExpandedUniverse::new(&galaxies, factor, factor).unwrap()
+    }
+
+    fn distances(universe: &ExpandedUniverse) -> Vec<u64> {
+        let count = universe.galaxies.len();
+        (0..count).flat_map(|i| (i + 1..count).map(move |j| (i, j))).map(|(i, j)| universe.distance(i, j)).collect()
+    }
+
+    #[test]
+    fn sums_match_the_puzzle() {
+        assert_eq!(test_universe(2).sum_of_distances(), Some(374));
+        assert_eq!(test_universe(10).sum_of_distances(), Some(1030));
+        assert_eq!(test_universe(100).sum_of_distances(), Some(8410));
+    }
+
+    #[test]
+    fn farthest_pair_is_the_longest_path() {
+        let universe = test_universe(2);
+        assert_eq!(universe.farthest_pair(), Some((1, 7, 19)));
+        for factor in [0, 1, 10, 1000000] {
+            let universe = test_universe(factor);
+            let (_, _, distance) = universe.farthest_pair().unwrap();
+            assert_eq!(Some(&distance), distances(&universe).iter().max());
+        }
+    }
+
+    #[test]
+    fn nearest_pair_is_the_shortest_path() {
+        // Three pairs are 5 apart, so only the distance is fixed
+        let universe = test_universe(2);
+        let (i, j, distance) = universe.nearest_pair().unwrap();
+        assert_eq!(distance, 5);
+        assert_eq!(universe.distance(i, j), 5);
+        for factor in [0, 1, 10, 1000000] {
+            let universe = test_universe(factor);
+            let (_, _, distance) = universe.nearest_pair().unwrap();
+            assert_eq!(Some(&distance), distances(&universe).iter().min());
+        }
+    }
+
+    #[test]
+    fn factors_past_the_coordinate_limit_are_rejected() {
+        let galaxies = [Point(0, 0), Point(2, 2)];
+        assert!(ExpandedUniverse::new(&galaxies, u64::MAX, 1).is_err());
+        assert!(ExpandedUniverse::new(&galaxies, 1, u64::MAX).is_err());
+        let universe = ExpandedUniverse::new(&galaxies, 1 << 60, 1 << 60).unwrap();
+        assert_eq!(universe.sum_of_distances(), Some((1 << 61) + 2));
+        assert_eq!(universe.farthest_pair(), Some((0, 1, (1 << 61) + 2)));
+    }
+}
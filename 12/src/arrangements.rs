@@ -0,0 +1,254 @@
+use rand::Rng;
+
+// Number type used to count arrangements. Additions are checked so that running out of bits is
+// reported instead of silently wrapping around
+pub trait Count: Copy {
+    const ZERO: Self;
+    const ONE: Self;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn is_zero(self) -> bool;
+}
+
+impl Count for u64 {
+    const ZERO: u64 = 0;
+    const ONE: u64 = 1;
+    fn checked_add(self, other: u64) -> Option<u64> { u64::checked_add(self, other) }
+    fn is_zero(self) -> bool { self == 0 }
+}
+
+// Only tracks whether there is any arrangement at all, so it can never overflow
+impl Count for bool {
+    const ZERO: bool = false;
+    const ONE: bool = true;
+    fn checked_add(self, other: bool) -> Option<bool> { Some(self || other) }
+    fn is_zero(self) -> bool { !self }
+}
+
+impl Count for u128 {
+    const ZERO: u128 = 0;
+    const ONE: u128 = 1;
+    fn checked_add(self, other: u128) -> Option<u128> { u128::checked_add(self, other) }
+    fn is_zero(self) -> bool { self == 0 }
+}
+
+// Ways of filling the row at a given position: either an operational spring, or a whole group of
+// broken ones followed by its delimiter
+#[derive(Copy, Clone, Debug)]
+enum Step {
+    Operational { next_position: usize },
+    Broken { group_len: usize, next_position: usize }
+}
+
+impl Step {
+    fn next_position(&self) -> usize {
+        match self {
+            Step::Operational { next_position } | Step::Broken { next_position, .. } => *next_position
+        }
+    }
+
+    fn next_group_index(&self, group_index: usize) -> usize {
+        match self {
+            Step::Operational { .. } => group_index,
+            Step::Broken { .. } => group_index + 1
+        }
+    }
+}
+
+// Dense table where the entry for (position, group_index) holds how many ways there are of
+// filling row[position..] so that it matches groups[group_index..]. Positions go up to
+// row.len() + 1 so that jumping over the delimiter after a group at the end of the row is valid
+pub struct ArrangementTable<C: Count> {
+    row: Vec<u8>,
+    groups: Vec<usize>,
+    // fillable[i] is how many characters starting at i could all be broken springs
+    fillable: Vec<usize>,
+    counts: Vec<C>
+}
+
+impl <C: Count> ArrangementTable<C> {
+    pub fn new(row: &[u8], groups: &[usize]) -> Option<ArrangementTable<C>> {
+        let row_len = row.len();
+        let mut fillable: Vec<usize> = vec![0; row_len + 1];
+        for position in (0..row_len).rev() {
+            fillable[position] = if row[position] == b'.' { 0 } else { fillable[position + 1] + 1 };
+        }
+        let mut table = ArrangementTable {
+            row: row.to_vec(),
+            groups: groups.to_vec(),
+            fillable,
+            counts: vec![C::ZERO; (row_len + 2) * (groups.len() + 1)]
+        };
+
+        for end in [row_len, row_len + 1] {
+            let index = table.index(end, groups.len());
+            table.counts[index] = C::ONE;
+        }
+        for position in (0..row_len).rev() {
+            for group_index in 0..=groups.len() {
+                let mut count: C = C::ZERO;
+                for step in table.steps(position, group_index).into_iter().flatten() {
+                    count = count.checked_add(table.get(step.next_position(), step.next_group_index(group_index)))?;
+                }
+                let index = table.index(position, group_index);
+                table.counts[index] = count;
+            }
+        }
+        Some(table)
+    }
+
+    pub fn get(&self, position: usize, group_index: usize) -> C { self.counts[self.index(position, group_index)] }
+
+    pub fn total(&self) -> C { self.get(0, 0) }
+
+    // Lazily walks every valid arrangement. Steps leading to a zero count are never taken, so
+    // every path that is explored ends in a valid filled row
+    pub fn iter(&self) -> Arrangements<'_, C> {
+        let mut arrangements = Arrangements { table: self, stack: Vec::new(), filled: Vec::new() };
+        arrangements.push_steps(0, 0);
+        arrangements
+    }
+
+    // Picks an arrangement uniformly at random, by choosing each step with a probability
+    // proportional to the number of arrangements that follow it
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Option<String> where C: Into<u128> {
+        let total: u128 = self.total().into();
+        if total == 0 {
+            return None;
+        }
+        let mut target: u128 = rng.gen_range(0..total);
+        let mut filled: Vec<u8> = Vec::with_capacity(self.row.len());
+        let (mut position, mut group_index) = (0, 0);
+        while position < self.row.len() {
+            let step: Step =
+                self.steps(position, group_index).into_iter()
+                    .flatten()
+                    .find(|step| {
+                        let count: u128 = self.get(step.next_position(), step.next_group_index(group_index)).into();
+                        if target < count {
+                            return true;
+                        }
+                        target -= count;
+                        false
+                    })
+                    .expect("Counts should add up to the total");
+            self.fill(&mut filled, step);
+            (position, group_index) = (step.next_position(), step.next_group_index(group_index));
+        }
+        Some(String::from_utf8(filled).unwrap())
+    }
+
+    // For each cell, whether it is broken in some arrangement and whether it is operational in
+    // some arrangement. Cells with only one option are forced. Returns None if there are no
+    // arrangements at all
+    pub fn cell_options(&self) -> Option<Vec<(bool, bool)>> {
+        if self.total().is_zero() {
+            return None;
+        }
+        let row_len = self.row.len();
+        let mut options: Vec<(bool, bool)> = vec![(false, false); row_len];
+        let mut reachable: Vec<bool> = vec![false; self.counts.len()];
+        reachable[self.index(0, 0)] = true;
+        for position in 0..row_len {
+            for group_index in 0..=self.groups.len() {
+                if !reachable[self.index(position, group_index)] {
+                    continue;
+                }
+                for step in self.steps(position, group_index).into_iter().flatten() {
+                    let next_group_index = step.next_group_index(group_index);
+                    if self.get(step.next_position(), next_group_index).is_zero() {
+                        continue;
+                    }
+                    reachable[self.index(step.next_position(), next_group_index)] = true;
+                    match step {
+                        Step::Operational { .. } => options[position].1 = true,
+                        Step::Broken { group_len, .. } => {
+                            options[position..position + group_len].iter_mut().for_each(|option| option.0 = true);
+                            if let Some(delimiter) = options.get_mut(position + group_len) {
+                                delimiter.1 = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Some(options)
+    }
+
+    fn steps(&self, position: usize, group_index: usize) -> [Option<Step>; 2] {
+        let operational =
+            Some(Step::Operational { next_position: position + 1 })
+                .filter(|_| self.row[position] != b'#');
+        let broken =
+            self.groups.get(group_index)
+                .filter(|_| self.row[position] != b'.')
+                .filter(|&&group_len| self.fillable[position] >= group_len)
+                .filter(|&&group_len| self.row.get(position + group_len) != Some(&b'#'))
+                .map(|&group_len| Step::Broken { group_len, next_position: position + group_len + 1 });
+        [operational, broken]
+    }
+
+    fn fill(&self, filled: &mut Vec<u8>, step: Step) {
+        match step {
+            Step::Operational { .. } => filled.push(b'.'),
+            Step::Broken { group_len, .. } => {
+                filled.extend(std::iter::repeat_n(b'#', group_len));
+                if filled.len() < self.row.len() {
+                    filled.push(b'.');
+                }
+            }
+        }
+    }
+
+    fn index(&self, position: usize, group_index: usize) -> usize { position * (self.groups.len() + 1) + group_index }
+}
+
+pub struct Arrangements<'a, C: Count> {
+    table: &'a ArrangementTable<C>,
+    // Steps still to be tried, along with the state they start from. Since every step fills the
+    // row up to its next position, the filled prefix is always as long as the starting position
+    stack: Vec<(usize, usize, Step)>,
+    filled: Vec<u8>
+}
+
+impl <'a, C: Count> Arrangements<'a, C> {
+    fn push_steps(&mut self, position: usize, group_index: usize) {
+        if position >= self.table.row.len() {
+            return;
+        }
+        // Pushed in reverse so that operational springs are tried first
+        for step in self.table.steps(position, group_index).into_iter().flatten().rev() {
+            if !self.table.get(step.next_position(), step.next_group_index(group_index)).is_zero() {
+                self.stack.push((position, group_index, step));
+            }
+        }
+    }
+}
+
+impl <'a, C: Count> Iterator for Arrangements<'a, C> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while let Some((position, group_index, step)) = self.stack.pop() {
+            self.filled.truncate(position);
+            self.table.fill(&mut self.filled, step);
+            let next_position = step.next_position();
+            let next_group_index = step.next_group_index(group_index);
+            if next_position >= self.table.row.len() {
+                return Some(String::from_utf8(self.filled.clone()).unwrap());
+            }
+            self.push_steps(next_position, next_group_index);
+        }
+        None
+    }
+}
+
+pub fn count_arrangements<C: Count>(row: &[u8], groups: &[usize]) -> Option<C> {
+    ArrangementTable::<C>::new(row, groups).map(|table| table.total())
+}
+
+// Repeats the row `factor` times joined by '?', and the groups `factor` times
+pub fn unfold(row: &str, groups: &[usize], factor: usize) -> (String, Vec<usize>) {
+    let unfolded_row: String = vec![row; factor].join("?");
+    let unfolded_groups: Vec<usize> = groups.repeat(factor);
+    (unfolded_row, unfolded_groups)
+}
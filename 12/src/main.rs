@@ -1,118 +1,203 @@
 use std::collections::HashMap;
 use std::env;
 use std::fs;
-use regex::Regex;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+use arrangements::{ArrangementTable, Count, count_arrangements, unfold};
+use matcher::GroupMatcher;
+use nonogram::{Nonogram, Solution};
+
+mod arrangements;
+mod matcher;
+mod nonogram;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let contents: String =
         fs::read_to_string(&args[1])
             .expect("should be able to read the file");
+    if args[2] == "nonogram" {
+        // The first file has the row clues, and this one the column clues
+        let column_clues: String = fs::read_to_string(&args[3]).expect("should be able to read the file");
+        let nonogram: Nonogram =
+            Nonogram::parse(&contents, &column_clues)
+                .unwrap_or_else(|error| panic!("Invalid nonogram in {} and {}: {error}", args[1], args[3]));
+        solve_nonogram(&nonogram);
+        return;
+    }
     let version: u64 = args[2].parse().expect("Should have a problem version");
-    let parsed_lines: Vec<(String, Vec<u32>)> = contents.trim().split("\n").map(parse_line).collect();
-
-    match version {
-        1 => {
-            let result: u64 =
-                parsed_lines.iter()
-                    .map(|(row, groups)| count_matching_variations_dyn_prog(row, groups))
-                    .sum();
-            println!("Result is {result}");
-        },
-        2 => {
-            let result: u64 =
-            parsed_lines.iter()
-                .map(|(row, groups)| {
-                    let mut row_times5 = row.clone();
-                    let mut groups_times5 = groups.clone();
-                    for _ in 0..4 {
-                        row_times5.push('?');
-                        row_times5.push_str(row);
-                        groups_times5.extend(groups);
-                    }
-                    count_matching_variations_dyn_prog(&row_times5, &groups_times5)
-                })
-                .sum();
-            println!("Result is {result}");
-        },
+    let parsed_lines: Vec<(String, Vec<usize>)> = contents.trim().split('\n').map(parse_line).collect();
+
+    let default_unfold: usize = match version {
+        1 => 1,
+        2 => 5,
         _ => panic!("Unknown version {version}")
     };
+    let unfold_factor: usize = parse_flag(&args, "--unfold").unwrap_or(default_unfold);
+    let unfolded_lines: Vec<(String, Vec<usize>)> =
+        parsed_lines.iter()
+            .map(|(row, groups)| unfold(row, groups, unfold_factor))
+            .collect();
+
+    if has_flag(&args, "--enumerate") || has_flag(&args, "--sample") {
+        let filled_lines: Vec<String> =
+            if has_flag(&args, "--enumerate") {
+                // The limit applies to each row, not to the whole output
+                let limit: usize = parse_flag(&args, "--limit").unwrap_or(usize::MAX);
+                enumerate_arrangements(&unfolded_lines, limit)
+            } else {
+                let mut rng: StdRng = match parse_flag(&args, "--seed") {
+                    Some(seed) => StdRng::seed_from_u64(seed),
+                    None => StdRng::from_entropy()
+                };
+                sample_arrangements(&unfolded_lines, &mut rng)
+            };
+        let output: String = filled_lines.iter().map(|line| line.clone() + "\n").collect();
+        match parse_flag::<String>(&args, "--output") {
+            Some(path) => fs::write(&path, output).expect("should be able to write the file"),
+            None => print!("{output}")
+        }
+        return;
+    }
+
+    if has_flag(&args, "--check") {
+        let matchers: HashMap<Vec<usize>, GroupMatcher> = compile_matchers(&unfolded_lines);
+        for (index, (row, groups)) in unfolded_lines.iter().enumerate() {
+            if let Some(position) = matchers[groups].first_impossible(row.as_bytes()) {
+                println!("Line {} can't match its groups from position {position}", index + 1);
+            }
+        }
+        return;
+    }
+
+    let method: String = parse_flag(&args, "--method").unwrap_or(String::from("dyn-prog"));
+    if method == "brute-force" {
+        let matchers: HashMap<Vec<usize>, GroupMatcher> = compile_matchers(&unfolded_lines);
+        let result: u64 =
+            unfolded_lines.iter()
+                .filter(|(row, groups)| row.first_impossible(&matchers[groups]).is_none())
+                .map(|(row, groups)| count_matching_variations_brute_force(row, &matchers[groups]) as u64)
+                .sum();
+        println!("Result is {result}");
+        return;
+    }
+
+    let count_type: String = parse_flag(&args, "--count").unwrap_or(String::from("u64"));
+    let result: Option<String> = match count_type.as_str() {
+        "u64" => sum_arrangements::<u64>(&unfolded_lines).map(|sum| sum.to_string()),
+        "u128" => sum_arrangements::<u128>(&unfolded_lines).map(|sum| sum.to_string()),
+        _ => panic!("Unknown count type {count_type}")
+    };
+    match result {
+        Some(result) => println!("Result is {result}"),
+        None if count_type == "u64" => eprintln!("Result does not fit in u64, try --count u128"),
+        None => eprintln!("Result does not fit in {count_type}")
+    }
+}
+
+fn parse_flag<T: std::str::FromStr>(args: &[String], name: &str) -> Option<T> {
+    args.iter()
+        .position(|arg| arg == name)
+        .map(|index| args.get(index + 1).expect("Flag should have a value"))
+        .map(|value| value.parse().unwrap_or_else(|_| panic!("Invalid value {value} for {name}")))
 }
 
-fn parse_line(line: &str) -> (String, Vec<u32>) {
+fn has_flag(args: &[String], name: &str) -> bool { args.iter().any(|arg| arg == name) }
+
+fn parse_line(line: &str) -> (String, Vec<usize>) {
     let parts: Vec<&str> = line.split(' ').collect();
-    let broken_spring_groups: Vec<u32> =
-        parts[1].split(',').map(|e| e.parse::<u32>().expect("Should be a number")).collect();
+    let broken_spring_groups: Vec<usize> =
+        parts[1].split(',').map(|e| e.parse::<usize>().expect("Should be a number")).collect();
     (String::from(parts[0]), broken_spring_groups)
 }
 
-fn count_matching_variations_dyn_prog(row: &str, groups: &[u32]) -> u64 {
-    count_matching_variations_dyn_prog_rec(row, groups, &mut HashMap::new())
+fn sum_arrangements<C: Count>(lines: &[(String, Vec<usize>)]) -> Option<C> {
+    lines.iter()
+        .try_fold(C::ZERO, |acc, (row, groups)| acc.checked_add(count_arrangements::<C>(row.as_bytes(), groups)?))
 }
 
-fn count_matching_variations_dyn_prog_rec(row: &str, groups: &[u32], cache: &mut HashMap<(String, Vec<u32>), u64>) -> u64 {
-    let target_group_len: usize = match groups.first() {
-        None => return if row.contains('#') { 0 } else { 1 },
-        Some(&val) => val as usize
-    };
-    let cache_key: (String, Vec<u32>) = (String::from(row), Vec::from(groups));
-    if cache.contains_key(&cache_key) {
-        return cache.get(&cache_key).cloned().unwrap();
-    }
+// Lines in the same format as the input, with every unknown spring filled in. Walking them only
+// needs to know which steps lead to an arrangement, so the table doesn't count them
+fn enumerate_arrangements(lines: &[(String, Vec<usize>)], limit: usize) -> Vec<String> {
+    lines.iter()
+        .flat_map(|(row, groups)| {
+            let table = ArrangementTable::<bool>::new(row.as_bytes(), groups).expect("Presence should never overflow");
+            table.iter()
+                .take(limit)
+                .map(|filled| format_line(&filled, groups))
+                .collect::<Vec<String>>()
+        })
+        .collect()
+}
 
-    if row.is_empty() || row.len() < target_group_len {
-        return 0;
-    }
-    if &row[0..1] == "." {
-        let cache_value: u64 = count_matching_variations_dyn_prog_rec(&row[1..], groups, cache);
-        cache.insert(cache_key, cache_value);
-        return cache_value;
-    }
-    if &row[0..1] == "?" {
-        let fork1 =
-            count_matching_variations_dyn_prog_rec( &(String::from("#") + &row[1..]), groups, cache);
-        let fork2 =
-            count_matching_variations_dyn_prog_rec(&(String::from(".") + &row[1..]), groups, cache);
-        return fork2 + fork1;
-    }
+fn sample_arrangements(lines: &[(String, Vec<usize>)], rng: &mut StdRng) -> Vec<String> {
+    // Rows without any valid arrangement, or with too many to weigh, are reported and left out
+    lines.iter()
+        .enumerate()
+        .filter_map(|(index, (row, groups))| {
+            let Some(table) = ArrangementTable::<u128>::new(row.as_bytes(), groups) else {
+                eprintln!("Row {} ({}) has too many arrangements to count in u128", index + 1, format_line(row, groups));
+                return None;
+            };
+            let filled: Option<String> = table.sample(rng);
+            if filled.is_none() {
+                eprintln!("Row {} ({}) has no valid arrangements", index + 1, format_line(row, groups));
+            }
+            filled.map(|filled| format_line(&filled, groups))
+        })
+        .collect()
+}
 
-    // Here the current character must be a #
-    let fits_group = row[..target_group_len].chars().all(|c| c == '#' || c == '?');
-    let is_followed_by_delimiter =
-        row.chars().nth(target_group_len).map(|c| c == '.' || c == '?').unwrap_or(true);
-    if !fits_group || !is_followed_by_delimiter {
-        return 0;
+fn solve_nonogram(nonogram: &Nonogram) {
+    match nonogram.solve() {
+        Solution::None => println!("Puzzle has no solution"),
+        Solution::Unique(picture) => print!("{picture}"),
+        Solution::Multiple(first, second) => {
+            println!("Puzzle has multiple solutions, including:");
+            print!("{first}");
+            println!();
+            print!("{second}");
+        }
     }
+}
+
+fn format_line(row: &str, groups: &[usize]) -> String {
+    let groups: Vec<String> = groups.iter().map(|group| group.to_string()).collect();
+    format!("{row} {}", groups.join(","))
+}
 
-    let mut next_row = &row[target_group_len..];
-    if !next_row.is_empty() {
-        next_row = &next_row[1..];
+// Lines with the same groups share their matcher
+fn compile_matchers(lines: &[(String, Vec<usize>)]) -> HashMap<Vec<usize>, GroupMatcher> {
+    let mut matchers: HashMap<Vec<usize>, GroupMatcher> = HashMap::new();
+    for (_, groups) in lines {
+        matchers.entry(groups.clone()).or_insert_with(|| GroupMatcher::compile(groups));
     }
-    let variations_keeping = count_matching_variations_dyn_prog_rec(next_row, &groups[1..], cache);
-    variations_keeping
+    matchers
 }
 
-fn count_matching_variations_brute_force(row: &String, groups: &Vec<u32>) -> u32 {
-    if !row.matches_groups(groups) {
-        return 0;
+// The row is one that can still match. Forks only fill in unknown springs, so a fork that the
+// matcher rejects is dropped along with all of its own forks
+fn count_matching_variations_brute_force(row: &String, matcher: &GroupMatcher) -> u32 {
+    match row.fork() {
+        None => 1,
+        Some((var1, var2)) =>
+            [var1, var2].iter()
+                .filter(|variation| variation.first_impossible(matcher).is_none())
+                .map(|variation| count_matching_variations_brute_force(variation, matcher))
+                .sum()
     }
-    row.fork()
-        .map(|(var1, var2)| count_matching_variations_brute_force(&var1, groups) + count_matching_variations_brute_force(&var2, groups))
-        .unwrap_or(1)
 }
 
 trait SpringRow {
-    fn is_filled(&self) -> bool;
     fn fork(&self) -> Option<(Self, Self)> where Self: Sized;
-    fn matches_groups(&self, broken_groups: &[u32]) -> bool;
+    fn first_impossible(&self, matcher: &GroupMatcher) -> Option<usize>;
 }
 
 impl SpringRow for String {
-    fn is_filled(&self) -> bool { !self.contains('?') }
-
     fn fork(&self) -> Option<(Self, Self)> where Self: Sized {
         self
-            .find(|c| c == '?')
+            .find('?')
             .map(|index| {
                 let left = &self[..index];
                 let right = &self[index + 1..];
@@ -120,16 +205,5 @@ impl SpringRow for String {
             })
     }
 
-    fn matches_groups(&self, broken_groups: &[u32]) -> bool {
-        let prefix_pattern = "^[\\.|?]*".to_string();
-        let middle_pattern: String = 
-            broken_groups.iter()
-                .map(|group_size| format!("[#|?]{{{group_size}}}"))
-                .collect::<Vec<String>>()
-                .join("[\\.|?]+");
-        let suffix_pattern: &str = "[\\.|?]*$";
-        let row_pattern = prefix_pattern + &middle_pattern + suffix_pattern;
-        let regex = Regex::new(&row_pattern).unwrap();
-        regex.is_match(self)
-    }
-}
\ No newline at end of file
+    fn first_impossible(&self, matcher: &GroupMatcher) -> Option<usize> { matcher.first_impossible(self.as_bytes()) }
+}
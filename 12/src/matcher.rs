@@ -0,0 +1,75 @@
+// Automaton accepting the rows that match a list of groups, i.e. the pattern
+// [.?]* [#?]{g1} [.?]+ [#?]{g2} ... [#?]{gn} [.?]*
+// The tokens are the states of the automaton, and being past the last token means accepting
+pub struct GroupMatcher {
+    tokens: Vec<Token>
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Token {
+    // Exactly one spring of the given kind
+    Broken,
+    Operational,
+    // Any number of operational springs, including none
+    OperationalRun
+}
+
+impl GroupMatcher {
+    pub fn compile(groups: &[usize]) -> GroupMatcher {
+        let mut tokens: Vec<Token> = vec![Token::OperationalRun];
+        for (index, &group_len) in groups.iter().enumerate() {
+            if index > 0 {
+                tokens.extend([Token::Operational, Token::OperationalRun]);
+            }
+            tokens.extend(std::iter::repeat_n(Token::Broken, group_len));
+        }
+        if !groups.is_empty() {
+            tokens.push(Token::OperationalRun);
+        }
+        GroupMatcher { tokens }
+    }
+
+    // Position of the first character after which no way of filling in the unknown springs can
+    // match anymore. A position equal to the row length means the row ended too early
+    pub fn first_impossible(&self, row: &[u8]) -> Option<usize> {
+        let mut active: Vec<bool> = vec![false; self.tokens.len() + 1];
+        active[0] = true;
+        self.close(&mut active);
+        for (position, &spring) in row.iter().enumerate() {
+            let mut next: Vec<bool> = vec![false; active.len()];
+            for (state, token) in self.tokens.iter().enumerate() {
+                if !active[state] || !token.accepts(spring) {
+                    continue;
+                }
+                match token {
+                    Token::OperationalRun => next[state] = true,
+                    Token::Broken | Token::Operational => next[state + 1] = true
+                }
+            }
+            self.close(&mut next);
+            if !next.contains(&true) {
+                return Some(position);
+            }
+            active = next;
+        }
+        if active[self.tokens.len()] { None } else { Some(row.len()) }
+    }
+
+    // Runs of operational springs can be skipped over without consuming anything
+    fn close(&self, active: &mut [bool]) {
+        for (state, token) in self.tokens.iter().enumerate() {
+            if active[state] && *token == Token::OperationalRun {
+                active[state + 1] = true;
+            }
+        }
+    }
+}
+
+impl Token {
+    fn accepts(&self, spring: u8) -> bool {
+        match self {
+            Token::Broken => spring == b'#' || spring == b'?',
+            Token::Operational | Token::OperationalRun => spring == b'.' || spring == b'?'
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::arrangements::ArrangementTable;
+
+// Each row and each column of a nonogram is a spring row: '#' is a filled cell, '.' an empty
+// one and '?' a cell that is not known yet
+pub struct Nonogram {
+    row_clues: Vec<Vec<usize>>,
+    column_clues: Vec<Vec<usize>>
+}
+
+pub enum Solution {
+    None,
+    Unique(Picture),
+    // The first two solutions that were found
+    Multiple(Picture, Picture)
+}
+
+#[derive(Clone)]
+pub struct Picture {
+    cols: usize,
+    cells: Vec<u8>
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Line { Row(usize), Column(usize) }
+
+impl Line {
+    // Rows come first, followed by the columns
+    fn id(&self, rows: usize) -> usize {
+        match self {
+            Line::Row(row) => *row,
+            Line::Column(col) => rows + col
+        }
+    }
+}
+
+impl Nonogram {
+    // One clue per line, with the group lengths separated by commas. An empty line or a 0 stands
+    // for a line without any filled cells
+    pub fn parse(row_clues: &str, column_clues: &str) -> Result<Nonogram, String> {
+        let row_clues = parse_clues("row", row_clues)?;
+        let column_clues = parse_clues("column", column_clues)?;
+        // Every row clue has to fit in the width given by the number of columns, and the other way round
+        check_clues_fit("Row", &row_clues, column_clues.len())?;
+        check_clues_fit("Column", &column_clues, row_clues.len())?;
+        Ok(Nonogram { row_clues, column_clues })
+    }
+
+    pub fn solve(&self) -> Solution {
+        let blank = Picture { cols: self.column_clues.len(), cells: vec![b'?'; self.row_clues.len() * self.column_clues.len()] };
+        let mut solutions: Vec<Picture> = Vec::new();
+        self.solve_rec(blank, &mut solutions);
+        let mut solutions = solutions.into_iter();
+        match (solutions.next(), solutions.next()) {
+            (None, _) => Solution::None,
+            (Some(solution), None) => Solution::Unique(solution),
+            (Some(first), Some(second)) => Solution::Multiple(first, second)
+        }
+    }
+
+    // Propagates the constraints as far as they go, and then guesses the first unknown cell.
+    // Stops as soon as two solutions have been found
+    fn solve_rec(&self, mut picture: Picture, solutions: &mut Vec<Picture>) {
+        if !self.propagate(&mut picture) {
+            return;
+        }
+        let unknown: usize = match picture.cells.iter().position(|&cell| cell == b'?') {
+            None => {
+                solutions.push(picture);
+                return;
+            }
+            Some(index) => index
+        };
+        for guess in [b'#', b'.'] {
+            if solutions.len() >= 2 {
+                return;
+            }
+            let mut guessed = picture.clone();
+            guessed.cells[unknown] = guess;
+            self.solve_rec(guessed, solutions);
+        }
+    }
+
+    // Fills in every cell that is forced by its row or column, revisiting the crossing lines of
+    // each newly filled cell. Returns false if some line has no valid arrangement
+    fn propagate(&self, picture: &mut Picture) -> bool {
+        let rows = self.row_clues.len();
+        let cols = self.column_clues.len();
+        let mut queue: VecDeque<Line> = (0..rows).map(Line::Row).chain((0..cols).map(Line::Column)).collect();
+        let mut queued: Vec<bool> = vec![true; rows + cols];
+
+        while let Some(line) = queue.pop_front() {
+            let (indexes, clues): (Vec<usize>, &Vec<usize>) = match line {
+                Line::Row(row) => ((0..cols).map(|col| row * cols + col).collect(), &self.row_clues[row]),
+                Line::Column(col) => ((0..rows).map(|row| row * cols + col).collect(), &self.column_clues[col])
+            };
+            queued[line.id(rows)] = false;
+
+            let cells: Vec<u8> = indexes.iter().map(|&index| picture.cells[index]).collect();
+            let table = ArrangementTable::<bool>::new(&cells, clues).unwrap();
+            let options: Vec<(bool, bool)> = match table.cell_options() {
+                None => return false,
+                Some(options) => options
+            };
+            for (position, &index) in indexes.iter().enumerate() {
+                let forced: u8 = match (cells[position], options[position]) {
+                    (b'?', (true, false)) => b'#',
+                    (b'?', (false, true)) => b'.',
+                    _ => continue
+                };
+                picture.cells[index] = forced;
+                let crossing: Line = match line {
+                    Line::Row(_) => Line::Column(index % cols),
+                    Line::Column(_) => Line::Row(index / cols)
+                };
+                if !queued[crossing.id(rows)] {
+                    queued[crossing.id(rows)] = true;
+                    queue.push_back(crossing);
+                }
+            }
+        }
+        true
+    }
+}
+
+impl fmt::Display for Picture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.cells.chunks(self.cols) {
+            writeln!(f, "{}", String::from_utf8_lossy(row))?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_clues(kind: &str, text: &str) -> Result<Vec<Vec<usize>>, String> {
+    // Only the final newline ends the file, any empty line before it is a clue of its own
+    text.strip_suffix('\n')
+        .unwrap_or(text)
+        .split('\n')
+        .enumerate()
+        .map(|(index, line)| {
+            line.trim()
+                .split(',')
+                .filter(|group| !group.is_empty())
+                .map(|group| group.parse::<usize>()
+                    .map_err(|_| format!("Line {} of the {kind} clues: {group} is not a number", index + 1)))
+                .filter(|group| *group != Ok(0))
+                .collect()
+        })
+        .collect()
+}
+
+fn check_clues_fit(kind: &str, clues: &[Vec<usize>], length: usize) -> Result<(), String> {
+    for (index, groups) in clues.iter().enumerate() {
+        let needed: usize = groups.iter().sum::<usize>() + groups.len().saturating_sub(1);
+        if needed > length {
+            return Err(format!("{kind} {} needs {needed} cells, but there are only {length}", index + 1));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trailing_empty_clues_are_kept() {
+        let nonogram = Nonogram::parse("1\n\n", "1\n0\n").unwrap();
+        match nonogram.solve() {
+            Solution::Unique(picture) => assert_eq!(picture.to_string(), "#.\n..\n"),
+            _ => panic!("Puzzle should have a unique solution")
+        }
+    }
+
+    #[test]
+    fn clues_longer_than_the_picture_are_rejected() {
+        assert_eq!(
+            Nonogram::parse("3\n1\n", "1\n1\n").err(),
+            Some("Row 1 needs 3 cells, but there are only 2".to_string())
+        );
+        assert_eq!(
+            Nonogram::parse("1\n1\n", "1,1\n").err(),
+            Some("Column 1 needs 3 cells, but there are only 2".to_string())
+        );
+    }
+
+    #[test]
+    fn clues_that_are_not_numbers_are_rejected() {
+        assert_eq!(
+            Nonogram::parse("1\n1,x\n", "1\n1\n").err(),
+            Some("Line 2 of the row clues: x is not a number".to_string())
+        );
+        assert_eq!(
+            Nonogram::parse("1\n1\n", "1\n-1\n").err(),
+            Some("Line 2 of the column clues: -1 is not a number".to_string())
+        );
+    }
+}
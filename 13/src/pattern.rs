@@ -0,0 +1,68 @@
+// Terrain where every row and every column is encoded as a bitmask with a bit set for each rock.
+// Bit i of a row is column i, and bit i of a column is row i, counting from the top left
+pub struct Pattern {
+    pub rows: Vec<u64>,
+    pub cols: Vec<u64>
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Axis { Vertical, Horizontal }
+
+// Mirror line between line `position - 1` and line `position`, where the lines are columns for
+// a vertical mirror and rows for a horizontal one. Mismatches are the tiles that differ from
+// their reflection on one of the sides
+#[derive(Copy, Clone, Debug)]
+pub struct Reflection {
+    pub axis: Axis,
+    pub position: usize,
+    pub mismatches: u32
+}
+
+impl Pattern {
+    pub fn parse(lines: &[String]) -> Pattern {
+        let height = lines.len();
+        let width = lines[0].len();
+        if width > 64 || height > 64 {
+            panic!("Patterns can't be larger than 64x64, got {width}x{height}");
+        }
+        let mut rows: Vec<u64> = vec![0; height];
+        let mut cols: Vec<u64> = vec![0; width];
+        for (row, line) in lines.iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                if c != '.' {
+                    rows[row] |= 1 << col;
+                    cols[col] |= 1 << row;
+                }
+            }
+        }
+        Pattern { rows, cols }
+    }
+
+    pub fn cell(&self, row: usize, col: usize) -> bool { self.rows[row] >> col & 1 == 1 }
+
+    pub fn reflections(&self) -> Vec<Reflection> {
+        let vertical = Self::reflections_along(&self.cols, Axis::Vertical);
+        let horizontal = Self::reflections_along(&self.rows, Axis::Horizontal);
+        vertical.chain(horizontal).collect()
+    }
+
+    fn reflections_along(lines: &[u64], axis: Axis) -> impl Iterator<Item = Reflection> + '_ {
+        (1..lines.len()).map(move |position| {
+            let mismatches: u32 =
+                lines[..position].iter().rev()
+                    .zip(lines[position..].iter())
+                    .map(|(before, after)| (before ^ after).count_ones())
+                    .sum();
+            Reflection { axis, position, mismatches }
+        })
+    }
+}
+
+impl Reflection {
+    pub fn score(&self) -> usize {
+        match self.axis {
+            Axis::Vertical => self.position,
+            Axis::Horizontal => 100 * self.position
+        }
+    }
+}
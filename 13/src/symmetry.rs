@@ -0,0 +1,251 @@
+use std::fmt;
+
+use crate::pattern::Pattern;
+
+// Symmetries other than mirror lines across the whole pattern. Mirrors of a sub-rectangle are
+// scored like the puzzle, by the columns left of a vertical axis or 100 times the rows above a
+// horizontal one. Diagonals have no lines on one side of them, so they score the length of the
+// diagonal, and rotations score the area of their region
+#[derive(Copy, Clone, Debug)]
+pub enum Symmetry {
+    // Mirror across the diagonal from the top left to the bottom right of a square pattern
+    MainDiagonal { mismatches: u32 },
+    // Mirror across the diagonal from the top right to the bottom left of a square pattern
+    AntiDiagonal { mismatches: u32 },
+    // Rotation about a centre given in doubled coordinates, so that odd values are half-integers.
+    // The region is the largest rectangle around the centre that stays in bounds when rotated
+    Rotation { degrees: u32, centre: (usize, usize), rows: usize, cols: usize, mismatches: u32 },
+    // Sub-rectangle that is mirrored across a line through its middle
+    SubRectangle { top_left: (usize, usize), rows: usize, cols: usize, vertical_mirror: bool, mismatches: u32 }
+}
+
+impl Symmetry {
+    pub fn score(&self, pattern: &Pattern) -> usize {
+        match self {
+            Symmetry::MainDiagonal { .. } | Symmetry::AntiDiagonal { .. } => pattern.rows.len(),
+            Symmetry::Rotation { rows, cols, .. } => rows * cols,
+            // With an odd width the axis goes through the middle line, which is on neither side
+            Symmetry::SubRectangle { top_left, cols, vertical_mirror: true, .. } => top_left.1 + cols / 2,
+            Symmetry::SubRectangle { top_left, rows, vertical_mirror: false, .. } => 100 * (top_left.0 + rows / 2)
+        }
+    }
+
+    fn area(&self, pattern: &Pattern) -> usize {
+        match self {
+            Symmetry::MainDiagonal { .. } | Symmetry::AntiDiagonal { .. } => pattern.rows.len() * pattern.cols.len(),
+            Symmetry::Rotation { rows, cols, .. } | Symmetry::SubRectangle { rows, cols, .. } => rows * cols
+        }
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Symmetry::MainDiagonal { mismatches } =>
+                write!(f, "Main diagonal mirror: {mismatches} mismatches"),
+            Symmetry::AntiDiagonal { mismatches } =>
+                write!(f, "Anti-diagonal mirror: {mismatches} mismatches"),
+            Symmetry::Rotation { degrees, centre, rows, cols, mismatches } =>
+                write!(f, "{degrees} degree rotation about ({}, {}) over {rows}x{cols} tiles: {mismatches} mismatches",
+                    centre.0 as f32 / 2.0, centre.1 as f32 / 2.0),
+            Symmetry::SubRectangle { top_left, rows, cols, vertical_mirror, mismatches } => {
+                let kind = if *vertical_mirror { "Vertical" } else { "Horizontal" };
+                write!(f, "{kind} mirror over {rows}x{cols} tiles from row {} and column {}: {mismatches} mismatches",
+                    top_left.0, top_left.1)
+            }
+        }
+    }
+}
+
+// Mismatches are counted as the cells that differ from their image, halved, so that a single
+// smudge counts once
+pub fn find_diagonal_symmetries(pattern: &Pattern, smudges: u32) -> Vec<Symmetry> {
+    let size = pattern.rows.len();
+    if size != pattern.cols.len() {
+        return Vec::new();
+    }
+    let count_mismatches = |image: &dyn Fn(usize, usize) -> (usize, usize)| -> u32 {
+        let differing: u32 =
+            cells(size, size)
+                .filter(|&(row, col)| {
+                    let (image_row, image_col) = image(row, col);
+                    pattern.cell(row, col) != pattern.cell(image_row, image_col)
+                })
+                .count() as u32;
+        differing / 2
+    };
+    let mut result: Vec<Symmetry> = Vec::new();
+    let main = count_mismatches(&|row, col| (col, row));
+    if main == smudges {
+        result.push(Symmetry::MainDiagonal { mismatches: main });
+    }
+    let anti = count_mismatches(&|row, col| (size - 1 - col, size - 1 - row));
+    if anti == smudges {
+        result.push(Symmetry::AntiDiagonal { mismatches: anti });
+    }
+    result
+}
+
+// Tries every integer and half-integer centre and keeps the one with the largest region. Regions
+// that are thinner than 2 cells are always symmetric and are skipped
+pub fn find_rotational_symmetry(pattern: &Pattern, degrees: u32, smudges: u32) -> Option<Symmetry> {
+    let height = pattern.rows.len();
+    let width = pattern.cols.len();
+    let mut best: Option<Symmetry> = None;
+    for centre_row in 0..=2 * (height - 1) {
+        for centre_col in 0..=2 * (width - 1) {
+            let candidate = match degrees {
+                180 => rotation_180(pattern, (centre_row, centre_col)),
+                90 => rotation_90(pattern, (centre_row, centre_col)),
+                _ => panic!("Unsupported rotation of {degrees} degrees")
+            };
+            if let Some(symmetry @ Symmetry::Rotation { rows, cols, mismatches, .. }) = candidate {
+                let is_valid = rows >= 2 && cols >= 2 && mismatches == smudges;
+                if is_valid && best.is_none_or(|best| best.area(pattern) < symmetry.area(pattern)) {
+                    best = Some(symmetry);
+                }
+            }
+        }
+    }
+    best
+}
+
+fn rotation_180(pattern: &Pattern, centre: (usize, usize)) -> Option<Symmetry> {
+    let (height, width) = (pattern.rows.len(), pattern.cols.len());
+    let first_row = centre.0.saturating_sub(height - 1);
+    let last_row = centre.0.min(height - 1);
+    let first_col = centre.1.saturating_sub(width - 1);
+    let last_col = centre.1.min(width - 1);
+    let differing: u32 =
+        (first_row..=last_row)
+            .flat_map(|row| (first_col..=last_col).map(move |col| (row, col)))
+            .filter(|&(row, col)| pattern.cell(row, col) != pattern.cell(centre.0 - row, centre.1 - col))
+            .count() as u32;
+    Some(Symmetry::Rotation {
+        degrees: 180,
+        centre,
+        rows: last_row - first_row + 1,
+        cols: last_col - first_col + 1,
+        mismatches: differing / 2
+    })
+}
+
+fn rotation_90(pattern: &Pattern, centre: (usize, usize)) -> Option<Symmetry> {
+    // Rotating a cell keeps it on the grid only if both coordinates of the centre are integers,
+    // or both are half-integers
+    if centre.0 % 2 != centre.1 % 2 {
+        return None;
+    }
+    let (height, width) = (pattern.rows.len(), pattern.cols.len());
+    // Largest distance from the centre, in doubled coordinates, that stays within the pattern
+    let extent = centre.0.min(2 * (height - 1) - centre.0).min(centre.1).min(2 * (width - 1) - centre.1);
+    let first_row = (centre.0 - extent).div_ceil(2);
+    let first_col = (centre.1 - extent).div_ceil(2);
+    let size = (centre.0 + extent) / 2 - first_row + 1;
+    let differing: u32 =
+        (first_row..first_row + size)
+            .flat_map(|row| (first_col..first_col + size).map(move |col| (row, col)))
+            .filter(|&(row, col)| {
+                let row_offset = 2 * row as i64 - centre.0 as i64;
+                let col_offset = 2 * col as i64 - centre.1 as i64;
+                let image_row = ((centre.0 as i64 + col_offset) / 2) as usize;
+                let image_col = ((centre.1 as i64 - row_offset) / 2) as usize;
+                pattern.cell(row, col) != pattern.cell(image_row, image_col)
+            })
+            .count() as u32;
+    Some(Symmetry::Rotation { degrees: 90, centre, rows: size, cols: size, mismatches: differing / 2 })
+}
+
+// For every mirror axis, counts the mismatches of each line within every distance of the axis.
+// Each block of consecutive lines then spans the widest distance at which its lines add up to
+// the given number of smudges
+pub fn find_largest_symmetric_sub_rectangle(pattern: &Pattern, smudges: u32) -> Option<Symmetry> {
+    let (height, width) = (pattern.rows.len(), pattern.cols.len());
+    let vertical = largest_mirrored_block(height, width, smudges, |row, col| pattern.cell(row, col));
+    let horizontal = largest_mirrored_block(width, height, smudges, |col, row| pattern.cell(row, col));
+
+    let vertical = vertical.map(|(first_line, lines, first_cell, cells)| Symmetry::SubRectangle {
+        top_left: (first_line, first_cell), rows: lines, cols: cells, vertical_mirror: true, mismatches: smudges
+    });
+    let horizontal = horizontal.map(|(first_line, lines, first_cell, cells)| Symmetry::SubRectangle {
+        top_left: (first_cell, first_line), rows: cells, cols: lines, vertical_mirror: false, mismatches: smudges
+    });
+    [vertical, horizontal].into_iter()
+        .flatten()
+        .max_by_key(|symmetry| symmetry.area(pattern))
+}
+
+// Returns (first line, line count, first cell, cell count) of the largest block, where the lines
+// mirrored in the same span of cells have exactly `smudges` mismatches between them
+fn largest_mirrored_block<F: Fn(usize, usize) -> bool>(
+    line_count: usize,
+    line_len: usize,
+    smudges: u32,
+    cell: F
+) -> Option<(usize, usize, usize, usize)> {
+    let mut best: Option<(usize, usize, usize, usize)> = None;
+    for axis in 0..2 * line_len - 1 {
+        let first_left = axis.div_ceil(2);
+        let first_right = axis / 2 + 1;
+        let max_radius = first_left.min(line_len - first_right);
+        // mismatches[line][radius] is how many of the first `radius` pairs around the axis differ
+        // in the lines before `line`, so that blocks of lines are a difference of two entries
+        let mut mismatches: Vec<Vec<u32>> = vec![vec![0; max_radius + 1]];
+        for line in 0..line_count {
+            let mut within: Vec<u32> = vec![0; max_radius + 1];
+            for radius in 1..=max_radius {
+                let differs = cell(line, first_left - radius) != cell(line, first_right + radius - 1);
+                within[radius] = within[radius - 1] + differs as u32;
+            }
+            let before: Vec<u32> = within.iter().zip(&mismatches[line]).map(|(within, before)| within + before).collect();
+            mismatches.push(before);
+        }
+        let centre_cells = if axis % 2 == 0 { 1 } else { 0 };
+        for first_line in 0..line_count {
+            for last_line in first_line..line_count {
+                // Mismatches only grow with the radius, so the widest matching span is the last one
+                let radius: Option<usize> =
+                    (1..=max_radius).rev()
+                        .find(|&radius| mismatches[last_line + 1][radius] - mismatches[first_line][radius] == smudges);
+                let Some(radius) = radius else { continue };
+                let cells = 2 * radius + centre_cells;
+                let lines = last_line - first_line + 1;
+                if best.is_none_or(|(_, best_lines, _, best_cells)| best_lines * best_cells < lines * cells) {
+                    best = Some((first_line, lines, first_left - radius, cells));
+                }
+            }
+        }
+    }
+    best
+}
+
+fn cells(rows: usize, cols: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..rows).flat_map(move |row| (0..cols).map(move |col| (row, col)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Pattern {
+        Pattern::parse(&text.split('\n').map(String::from).collect::<Vec<String>>())
+    }
+
+    #[test]
+    fn sub_rectangles_are_scored_like_the_puzzle() {
+        let pattern = parse("#.##..##.\n..#.##.#.\n##......#\n##......#\n..#.##.#.\n..##..##.\n#.#.##.#.");
+        let symmetry = find_largest_symmetric_sub_rectangle(&pattern, 0).unwrap();
+        assert_eq!(symmetry.to_string(), "Vertical mirror over 7x8 tiles from row 0 and column 1: 0 mismatches");
+        assert_eq!(symmetry.score(&pattern), 5);
+    }
+
+    #[test]
+    fn sub_rectangles_allow_the_given_smudges() {
+        // Same pattern as above, where fixing the smudge gives the puzzle's second mirror
+        let pattern = parse("#.##..##.\n..#.##.#.\n##......#\n##......#\n..#.##.#.\n..##..##.\n#.#.##.#.");
+        let symmetry = find_largest_symmetric_sub_rectangle(&pattern, 1).unwrap();
+        assert_eq!(symmetry.to_string(), "Horizontal mirror over 6x9 tiles from row 0 and column 0: 1 mismatches");
+        assert_eq!(symmetry.score(&pattern), 300);
+        assert!(find_largest_symmetric_sub_rectangle(&parse("#.\n.#"), 3).is_none());
+    }
+}
@@ -1,15 +1,15 @@
-use std::cmp::Ordering;
-use std::collections::HashMap;
 use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::iter;
 
-use geometry::Bounds;
-use geometry::Point;
-use geometry::Vector;
+use spatial::geometry::GridDimensions;
+use spatial::geometry::Point;
+use cycle::Strategy;
+use platform::{Platform, Rocks, Tilt};
 
-mod geometry;
+mod platform;
+mod reference;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -18,186 +18,168 @@ fn main() {
             .expect("should be able to read the file");
     let version: u32 = args[2].parse().expect("Should have a problem version");
     let lines: Vec<&str> = contents.trim().split("\n").collect();
-    let (mobile_rocks, fixed_rocks) =
-        lines.iter()
-            .enumerate()
-            .map(|(index, line)| ((lines.len() - 1 - index) as f32, line))
-            .map(|(y, line)| parse_line(line, y))
-            .reduce(|mut a, b| {
-                a.0.extend(b.0);
-                a.1.extend(b.1);
-                (a.0, a.1)
-            })
-            .unwrap();
-    let top_right_tile = Point(lines.len() as f32 - 1.0, lines[0].len() as f32 - 1.0);
-    let bounds = Bounds::from_origin(&top_right_tile);
-
-    match version {
-        1 => {
-            let result: u32 =
-                tilt_north(&mobile_rocks, &fixed_rocks, &bounds).iter().map(|Point(_, y)| *y as u32 + 1).sum();
-            println!("Result is {result}");
-        },
-        2 => {
-            let looped_config = find_repeated_config(&mobile_rocks, &fixed_rocks, &bounds);
-            let remaining_cycles_after_loop: u32 = (1_000_000_000 - looped_config.offset) % looped_config.loop_len;
-            let final_config: HashSet<Point> =
-                iter::successors(
-                    Some(looped_config.configuration),
-                    |configuration| Some(tilt_cycle(configuration, &fixed_rocks, &bounds)))
-                    .nth(remaining_cycles_after_loop as usize)
-                    .unwrap();
-            let result: u32 = final_config.iter().map(|Point(_, y)| *y as u32 + 1).sum();
-            println!("Result is {result}");
-        },
+    let dimensions: GridDimensions =
+        GridDimensions::from_lines(&lines).unwrap_or_else(|error| panic!("Invalid grid in {}: {error}", args[1]));
+    // Version 1 is a single tilt to the north, and version 2 the spin cycle
+    let (default_program, default_cycles): (&str, u64) = match version {
+        1 => ("N", 1),
+        2 => ("NWSE", 1_000_000_000),
         _ => panic!("Unknown version {version}")
     };
-}
-
-fn parse_line(line: &str, y: f32) -> (HashSet<Point>, HashSet<Point>) {
-    let mut mobile_rocks: HashSet<Point> = HashSet::new();
-    let mut fixed_rocks: HashSet<Point> = HashSet::new();
-    for (x, c) in line.chars().enumerate() {
-        let x = x as f32;
-        let position = Point(x, y);
-        match c {
-            '.' => continue,
-            'O' => {
-                mobile_rocks.insert(position);
-            },
-            '#' => { fixed_rocks.insert(position); },
-            _ => panic!("Unexpected character {c}")
-        }
-    }
-    (mobile_rocks, fixed_rocks)
-}
+    let program_text: String = parse_flag(&args, "--program").unwrap_or(String::from(default_program));
+    let program: Vec<Tilt> =
+        Tilt::parse_program(&program_text)
+            .unwrap_or_else(|| panic!("Invalid program {program_text}, should be made of N, W, S and E"));
+    let cycles: u64 = parse_flag(&args, "--cycles").unwrap_or(default_cycles);
+    let edge_text: String = parse_flag(&args, "--edge").unwrap_or(String::from("N"));
+    let edge: Tilt = match Tilt::parse_program(&edge_text).as_deref() {
+        Some(&[edge]) => edge,
+        _ => panic!("Invalid edge {edge_text}, should be one of N, W, S and E")
+    };
+    let strategy: Strategy = parse_flag(&args, "--cycle-detection").unwrap_or(Strategy::HashMap);
+    let problem = Problem {
+        program,
+        cycles,
+        edge,
+        strategy,
+        show_history: has_flag(&args, "--history"),
+        show_memory: has_flag(&args, "--memory")
+    };
 
-fn find_repeated_config(
-    mobile_rocks: &HashSet<Point>,
-    fixed_rocks: &HashSet<Point>,
-    bounds: &Bounds
-) -> Loop {
-    let mut seen_configurations: HashMap<Vec<Point>, u32> = HashMap::new();
-    let mut current_configuration: HashSet<Point> = mobile_rocks.clone();
-    for iteration in 1_u32.. {
-        current_configuration = tilt_cycle(&current_configuration, fixed_rocks, bounds);
-        let mut hash: Vec<Point> = current_configuration.iter().cloned().collect();
-        hash.sort_by(|Point(x1, y1), Point(x2, y2)| {
-            let y_cmp = y1.total_cmp(y2);
-            if y_cmp != Ordering::Equal {
-                return y_cmp;
+    let engine: String = parse_flag(&args, "--engine").unwrap_or(String::from("bitboard"));
+    let result: u64 = match engine.as_str() {
+        "bitboard" => solve_with_bitboard(&lines, &dimensions, &problem),
+        "reference" => solve_with_reference(&lines, &dimensions, &problem),
+        "compare" => {
+            let bitboard = solve_with_bitboard(&lines, &dimensions, &problem);
+            let reference = solve_with_reference(&lines, &dimensions, &problem);
+            if bitboard != reference {
+                panic!("Bitboard engine found {bitboard}, but the reference found {reference}");
             }
-            x1.total_cmp(x2)
-        });
-
-        if seen_configurations.contains_key(&hash) {
-            let offset: u32 = *seen_configurations.get(&hash).unwrap();
-            let loop_len: u32 = iteration - offset;
-            return Loop { configuration: current_configuration, offset, loop_len };
-        }
-        seen_configurations.insert(hash, iteration);
-    }
-    panic!("Escaped an infinite loop!");
+            bitboard
+        },
+        _ => panic!("Unknown engine {engine}")
+    };
+    println!("Result is {result}");
 }
 
-struct Loop {
-    configuration: HashSet<Point>,
-    offset: u32,
-    loop_len: u32
+fn parse_flag<T: std::str::FromStr>(args: &[String], name: &str) -> Option<T> {
+    args.iter()
+        .position(|arg| arg == name)
+        .map(|index| args.get(index + 1).expect("Flag should have a value"))
+        .map(|value| value.parse().unwrap_or_else(|_| panic!("Invalid value {value} for {name}")))
 }
 
-fn tilt_cycle(
-    mobile_rocks: &HashSet<Point>,
-    fixed_rocks: &HashSet<Point>,
-    bounds: &Bounds
-) -> HashSet<Point> {
-    let tilted1 = tilt_north(mobile_rocks, fixed_rocks, bounds);
-    let tilted2 = tilt_west(&tilted1, fixed_rocks, bounds);
-    let tilted3 = tilt_south(&tilted2, fixed_rocks, bounds);
-    let tilted4 = tilt_east(&tilted3, fixed_rocks, bounds);
-    tilted4
-}
+fn has_flag(args: &[String], name: &str) -> bool { args.iter().any(|arg| arg == name) }
 
-fn tilt_north(
-    mobile_rocks: &HashSet<Point>,
-    fixed_rocks: &HashSet<Point>,
-    bounds: &Bounds
-) -> HashSet<Point> {
-    tilt(mobile_rocks, fixed_rocks, bounds, &bounds.top_right, &Vector::LEFT, &Vector::DOWN)
+// Applies the program the given number of times and measures the load against the edge
+struct Problem {
+    program: Vec<Tilt>,
+    cycles: u64,
+    edge: Tilt,
+    strategy: Strategy,
+    show_history: bool,
+    show_memory: bool
 }
 
-fn tilt_east(
-    mobile_rocks: &HashSet<Point>,
-    fixed_rocks: &HashSet<Point>,
-    bounds: &Bounds
-) -> HashSet<Point> {
-    tilt(mobile_rocks, fixed_rocks, bounds, &bounds.top_right, &Vector::DOWN, &Vector::LEFT)
+fn solve_with_bitboard(lines: &[&str], dimensions: &GridDimensions, problem: &Problem) -> u64 {
+    let (platform, rocks) = Platform::parse(lines, dimensions);
+    let spin = |rocks: &Rocks| {
+        let mut next = rocks.clone();
+        platform.spin_cycle(&mut next, &problem.program);
+        next
+    };
+    let (spin_loop, usage) = cycle::find_cycle(&rocks, spin, problem.strategy);
+    if problem.show_history {
+        let history = iter::successors(Some(rocks.clone()), |rocks| Some(spin(rocks)));
+        for (cycle, rocks) in history.take((spin_loop.offset + spin_loop.period) as usize + 1).enumerate() {
+            println!("Cycle {cycle}: load {}", platform.load(&rocks, problem.edge));
+        }
+        println!("Loop {{ offset: {}, loop_len: {} }}", spin_loop.offset, spin_loop.period);
+    }
+    if problem.show_memory {
+        println!("Cycle detection took {usage}");
+    }
+    platform.load(&spin_loop.state_after(&rocks, spin, problem.cycles), problem.edge)
 }
 
-fn tilt_south(
-    mobile_rocks: &HashSet<Point>,
-    fixed_rocks: &HashSet<Point>,
-    bounds: &Bounds
-) -> HashSet<Point> {
-    tilt(mobile_rocks, fixed_rocks, bounds, &bounds.bottom_left, &Vector::RIGHT, &Vector::UP)
+fn solve_with_reference(lines: &[&str], dimensions: &GridDimensions, problem: &Problem) -> u64 {
+    let (mobile_rocks, fixed_rocks) =
+        lines.iter()
+            .enumerate()
+            .map(|(index, line)| ((lines.len() - 1 - index) as f32, line))
+            .map(|(y, line)| reference::parse_line(line, y))
+            .reduce(|mut a, b| {
+                a.0.extend(b.0);
+                a.1.extend(b.1);
+                (a.0, a.1)
+            })
+            .unwrap();
+    let bounds = dimensions.bounds();
+
+    let spin = |configuration: &HashSet<Point>| reference::tilt_cycle(configuration, &fixed_rocks, &bounds, &problem.program);
+    let looped_config = reference::find_repeated_config(&mobile_rocks, &fixed_rocks, &bounds, &problem.program);
+    let offset = looped_config.offset as u64;
+    let (start, remaining_cycles): (HashSet<Point>, u64) =
+        if problem.cycles < offset {
+            (mobile_rocks, problem.cycles)
+        } else {
+            (looped_config.configuration, (problem.cycles - offset) % looped_config.loop_len as u64)
+        };
+    let final_config: HashSet<Point> =
+        iter::successors(Some(start), |configuration| Some(spin(configuration)))
+            .nth(remaining_cycles as usize)
+            .unwrap();
+    let (height, width) = (dimensions.height as f32, dimensions.width as f32);
+    final_config.iter()
+        .map(|Point(x, y)| match problem.edge {
+            Tilt::North => y + 1.0,
+            Tilt::South => height - y,
+            Tilt::West => width - x,
+            Tilt::East => x + 1.0
+        })
+        .map(|load| load as u64)
+        .sum()
 }
 
-fn tilt_west(
-    mobile_rocks: &HashSet<Point>,
-    fixed_rocks: &HashSet<Point>,
-    bounds: &Bounds
-) -> HashSet<Point> {
-    tilt(mobile_rocks, fixed_rocks, bounds, &bounds.bottom_left, &Vector::UP, &Vector::RIGHT)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn tilt(
-    mobile_rocks: &HashSet<Point>,
-    fixed_rocks: &HashSet<Point>,
-    bounds: &Bounds,
-    initial_pos: &Point,
-    initial_pos_step: &Vector,
-    scan_step: &Vector
-) -> HashSet<Point> {
-    let mut final_positions: HashSet<Point> = HashSet::new();
-    let initial_positions =
-        iter::successors(
-            Some(*initial_pos),
-            |pos| Some(*pos + *initial_pos_step).filter(|p| bounds.contains(p)));
+    fn read_lines(path: &str) -> Vec<String> {
+        let contents = fs::read_to_string(format!("{}/{path}", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        contents.trim().split('\n').map(String::from).collect()
+    }
 
-    for mut landing_pos in initial_positions {
-        let scan_positions =
-            iter::successors(
-                Some(landing_pos),
-                |pos| Some(*pos + *scan_step).filter(|p| bounds.contains(&p)));
-        for pos in scan_positions {
-            if mobile_rocks.contains(&pos) {
-                final_positions.insert(landing_pos);
-                landing_pos += *scan_step;
-            } else if fixed_rocks.contains(&pos) {
-                landing_pos = pos + *scan_step;
-            }
+    fn problem(program: &str, cycles: u64) -> Problem {
+        Problem {
+            program: Tilt::parse_program(program).unwrap(),
+            cycles,
+            edge: Tilt::North,
+            strategy: Strategy::HashMap,
+            show_history: false,
+            show_memory: false
         }
     }
-    final_positions
-}
 
-#[allow(dead_code)]
-fn to_string(mobile_rocks: &HashMap<Point, u32>, fixed_rocks: &HashSet<Point>, top_right_tile: &Point) -> String {
-    let mut result = String::new();
-    for y in (0..=top_right_tile.1 as i32).rev() {
-        for x in 0..=top_right_tile.0 as i32 {
-            let position = Point(x as f32, y as f32);
-            if mobile_rocks.contains_key(&position) {
-                result.push('O');
-                continue;
-            }
-            if fixed_rocks.contains(&position) {
-                result.push('#');
-                continue;
-            }
-            result.push('.');
+    #[test]
+    fn ragged_grids_are_rejected() {
+        let lines = read_lines("test_input_ragged.txt");
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        assert_eq!(
+            GridDimensions::from_lines(&lines),
+            Err(String::from("Line 4 is 12 tiles wide, but line 1 is 10 tiles wide")));
+    }
+
+    #[test]
+    fn rectangular_grids_are_solved_by_both_engines() {
+        let lines = read_lines("test_input_rectangular.txt");
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let dimensions = GridDimensions::from_lines(&lines).unwrap();
+        assert_eq!(dimensions, GridDimensions { width: 12, height: 7 });
+        for (problem, expected) in [(problem("N", 1), 94), (problem("NWSE", 1_000_000_000), 50)] {
+            assert_eq!(solve_with_bitboard(&lines, &dimensions, &problem), expected);
+            assert_eq!(solve_with_reference(&lines, &dimensions, &problem), expected);
         }
-        result.push('\n');
     }
-    return result;
-}
\ No newline at end of file
+}
@@ -0,0 +1,197 @@
+use std::hash::{Hash, Hasher};
+
+use spatial::geometry::GridDimensions;
+
+// Platform as a dense bitboard with one bit per tile, row by row starting from the north west
+// corner. The cube-shaped rocks never move, so the stretches of tiles between them are computed
+// once for each direction, and a tilt just packs the rounded rocks of each stretch against its
+// start
+pub struct Platform {
+    pub width: usize,
+    pub height: usize,
+    runs: [Vec<Run>; 4],
+    zobrist_keys: Vec<u64>
+}
+
+// Positions of the rounded rocks. The hash is the XOR of the Zobrist keys of every occupied tile,
+// and is kept up to date as the rocks move
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rocks {
+    bits: Vec<u64>,
+    hash: u64
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Tilt { North, West, South, East }
+
+// Tiles start, start + step, start + 2 * step, ... with the first one against a wall or a cube-shaped
+// rock in the tilt direction
+#[derive(Copy, Clone, Debug)]
+struct Run {
+    start: usize,
+    step: isize,
+    len: usize
+}
+
+impl Platform {
+    pub fn parse(lines: &[&str], dimensions: &GridDimensions) -> (Platform, Rocks) {
+        let GridDimensions { width, height } = *dimensions;
+        let mut fixed: Vec<u64> = vec![0; (width * height).div_ceil(64)];
+        let mut rocks = Rocks { bits: fixed.clone(), hash: 0 };
+        let zobrist_keys: Vec<u64> = zobrist_keys(width * height);
+        for (row, line) in lines.iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                let tile = row * width + col;
+                match c {
+                    '.' => continue,
+                    'O' => rocks.toggle(tile, zobrist_keys[tile]),
+                    '#' => fixed[tile / 64] |= 1 << (tile % 64),
+                    _ => panic!("Unexpected character {c}")
+                }
+            }
+        }
+        let runs = [Tilt::North, Tilt::West, Tilt::South, Tilt::East]
+            .map(|tilt| find_runs(width, height, &fixed, tilt));
+        (Platform { width, height, runs, zobrist_keys }, rocks)
+    }
+
+    pub fn tilt(&self, rocks: &mut Rocks, tilt: Tilt) {
+        for run in self.runs[tilt as usize].iter() {
+            let count = run.tiles().filter(|&tile| rocks.contains(tile)).count();
+            for (offset, tile) in run.tiles().enumerate() {
+                if rocks.contains(tile) != (offset < count) {
+                    rocks.toggle(tile, self.zobrist_keys[tile]);
+                }
+            }
+        }
+    }
+
+    pub fn spin_cycle(&self, rocks: &mut Rocks, program: &[Tilt]) {
+        for &tilt in program {
+            self.tilt(rocks, tilt);
+        }
+    }
+
+    // Each rounded rock contributes its distance in lines from the edge opposite to the given one,
+    // counting its own line
+    pub fn load(&self, rocks: &Rocks, edge: Tilt) -> u64 {
+        rocks.tiles()
+            .map(|tile| {
+                let (row, col) = (tile / self.width, tile % self.width);
+                match edge {
+                    Tilt::North => self.height - row,
+                    Tilt::South => row + 1,
+                    Tilt::West => self.width - col,
+                    Tilt::East => col + 1
+                }
+            })
+            .map(|load| load as u64)
+            .sum()
+    }
+}
+
+impl Tilt {
+    pub fn parse(c: char) -> Option<Tilt> {
+        match c {
+            'N' => Some(Tilt::North),
+            'W' => Some(Tilt::West),
+            'S' => Some(Tilt::South),
+            'E' => Some(Tilt::East),
+            _ => None
+        }
+    }
+
+    // Program such as "NWSE", with the tilts applied from left to right
+    pub fn parse_program(text: &str) -> Option<Vec<Tilt>> {
+        if text.is_empty() {
+            return None;
+        }
+        text.chars().map(Tilt::parse).collect()
+    }
+}
+
+impl Rocks {
+    pub fn contains(&self, tile: usize) -> bool { self.bits[tile / 64] >> (tile % 64) & 1 == 1 }
+
+    pub fn tiles(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.iter().enumerate().flat_map(|(index, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(index * 64 + bit)
+            })
+        })
+    }
+
+    fn toggle(&mut self, tile: usize, key: u64) {
+        self.bits[tile / 64] ^= 1 << (tile % 64);
+        self.hash ^= key;
+    }
+}
+
+// Equal rocks always have equal Zobrist hashes, so hashing just the hash is consistent with Eq
+impl Hash for Rocks {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.hash.hash(state); }
+}
+
+impl Run {
+    fn tiles(&self) -> impl Iterator<Item = usize> {
+        let Run { start, step, len } = *self;
+        (0..len as isize).map(move |offset| (start as isize + offset * step) as usize)
+    }
+}
+
+fn find_runs(width: usize, height: usize, fixed: &[u64], tilt: Tilt) -> Vec<Run> {
+    let (line_count, line_len) = match tilt {
+        Tilt::North | Tilt::South => (width, height),
+        Tilt::West | Tilt::East => (height, width)
+    };
+    let step: isize = match tilt {
+        Tilt::North => width as isize,
+        Tilt::South => -(width as isize),
+        Tilt::West => 1,
+        Tilt::East => -1
+    };
+    let mut runs: Vec<Run> = Vec::new();
+    for line in 0..line_count {
+        // Tile at the given distance from the wall the rocks roll towards
+        let tile = |offset: usize| -> usize {
+            match tilt {
+                Tilt::North => offset * width + line,
+                Tilt::South => (height - 1 - offset) * width + line,
+                Tilt::West => line * width + offset,
+                Tilt::East => line * width + width - 1 - offset
+            }
+        };
+        let mut run_start = 0;
+        for offset in 0..=line_len {
+            let is_blocked = offset == line_len || fixed[tile(offset) / 64] >> (tile(offset) % 64) & 1 == 1;
+            if !is_blocked {
+                continue;
+            }
+            if offset > run_start {
+                runs.push(Run { start: tile(run_start), step, len: offset - run_start });
+            }
+            run_start = offset + 1;
+        }
+    }
+    runs
+}
+
+// SplitMix64 with a fixed seed, so that hashes are the same from one run to the next
+fn zobrist_keys(count: usize) -> Vec<u64> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    (0..count)
+        .map(|_| {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut key = state;
+            key = (key ^ (key >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            key = (key ^ (key >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            key ^ (key >> 31)
+        })
+        .collect()
+}
@@ -0,0 +1,165 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::iter;
+
+use spatial::geometry::Bounds;
+use spatial::geometry::GridDimensions;
+use spatial::geometry::Point;
+use spatial::geometry::Vector;
+use crate::platform::Tilt;
+
+// Original implementation over sets of points, kept to cross-check the bitboard engine
+
+pub fn parse_line(line: &str, y: f32) -> (HashSet<Point>, HashSet<Point>) {
+    let mut mobile_rocks: HashSet<Point> = HashSet::new();
+    let mut fixed_rocks: HashSet<Point> = HashSet::new();
+    for (x, c) in line.chars().enumerate() {
+        let x = x as f32;
+        let position = Point(x, y);
+        match c {
+            '.' => continue,
+            'O' => {
+                mobile_rocks.insert(position);
+            },
+            '#' => { fixed_rocks.insert(position); },
+            _ => panic!("Unexpected character {c}")
+        }
+    }
+    (mobile_rocks, fixed_rocks)
+}
+
+pub fn find_repeated_config(
+    mobile_rocks: &HashSet<Point>,
+    fixed_rocks: &HashSet<Point>,
+    bounds: &Bounds,
+    program: &[Tilt]
+) -> Loop {
+    let mut seen_configurations: HashMap<Vec<Point>, u32> = HashMap::new();
+    let mut current_configuration: HashSet<Point> = mobile_rocks.clone();
+    for iteration in 1_u32.. {
+        current_configuration = tilt_cycle(&current_configuration, fixed_rocks, bounds, program);
+        let mut hash: Vec<Point> = current_configuration.iter().cloned().collect();
+        hash.sort_by(|Point(x1, y1), Point(x2, y2)| {
+            let y_cmp = y1.total_cmp(y2);
+            if y_cmp != Ordering::Equal {
+                return y_cmp;
+            }
+            x1.total_cmp(x2)
+        });
+
+        if seen_configurations.contains_key(&hash) {
+            let offset: u32 = *seen_configurations.get(&hash).unwrap();
+            let loop_len: u32 = iteration - offset;
+            return Loop { configuration: current_configuration, offset, loop_len };
+        }
+        seen_configurations.insert(hash, iteration);
+    }
+    panic!("Escaped an infinite loop!");
+}
+
+pub struct Loop {
+    pub configuration: HashSet<Point>,
+    pub offset: u32,
+    pub loop_len: u32
+}
+
+pub fn tilt_cycle(
+    mobile_rocks: &HashSet<Point>,
+    fixed_rocks: &HashSet<Point>,
+    bounds: &Bounds,
+    program: &[Tilt]
+) -> HashSet<Point> {
+    program.iter().fold(mobile_rocks.clone(), |rocks, tilt| {
+        match tilt {
+            Tilt::North => tilt_north(&rocks, fixed_rocks, bounds),
+            Tilt::West => tilt_west(&rocks, fixed_rocks, bounds),
+            Tilt::South => tilt_south(&rocks, fixed_rocks, bounds),
+            Tilt::East => tilt_east(&rocks, fixed_rocks, bounds)
+        }
+    })
+}
+
+fn tilt_north(
+    mobile_rocks: &HashSet<Point>,
+    fixed_rocks: &HashSet<Point>,
+    bounds: &Bounds
+) -> HashSet<Point> {
+    tilt(mobile_rocks, fixed_rocks, bounds, &bounds.top_right, &Vector::LEFT, &Vector::DOWN)
+}
+
+fn tilt_east(
+    mobile_rocks: &HashSet<Point>,
+    fixed_rocks: &HashSet<Point>,
+    bounds: &Bounds
+) -> HashSet<Point> {
+    tilt(mobile_rocks, fixed_rocks, bounds, &bounds.top_right, &Vector::DOWN, &Vector::LEFT)
+}
+
+fn tilt_south(
+    mobile_rocks: &HashSet<Point>,
+    fixed_rocks: &HashSet<Point>,
+    bounds: &Bounds
+) -> HashSet<Point> {
+    tilt(mobile_rocks, fixed_rocks, bounds, &bounds.bottom_left, &Vector::RIGHT, &Vector::UP)
+}
+
+fn tilt_west(
+    mobile_rocks: &HashSet<Point>,
+    fixed_rocks: &HashSet<Point>,
+    bounds: &Bounds
+) -> HashSet<Point> {
+    tilt(mobile_rocks, fixed_rocks, bounds, &bounds.bottom_left, &Vector::UP, &Vector::RIGHT)
+}
+
+fn tilt(
+    mobile_rocks: &HashSet<Point>,
+    fixed_rocks: &HashSet<Point>,
+    bounds: &Bounds,
+    initial_pos: &Point,
+    initial_pos_step: &Vector,
+    scan_step: &Vector
+) -> HashSet<Point> {
+    let mut final_positions: HashSet<Point> = HashSet::new();
+    let initial_positions =
+        iter::successors(
+            Some(*initial_pos),
+            |pos| Some(*pos + *initial_pos_step).filter(|p| bounds.contains(p)));
+
+    for mut landing_pos in initial_positions {
+        let scan_positions =
+            iter::successors(
+                Some(landing_pos),
+                |pos| Some(*pos + *scan_step).filter(|p| bounds.contains(p)));
+        for pos in scan_positions {
+            if mobile_rocks.contains(&pos) {
+                final_positions.insert(landing_pos);
+                landing_pos += *scan_step;
+            } else if fixed_rocks.contains(&pos) {
+                landing_pos = pos + *scan_step;
+            }
+        }
+    }
+    final_positions
+}
+
+#[allow(dead_code)]
+fn to_string(mobile_rocks: &HashMap<Point, u32>, fixed_rocks: &HashSet<Point>, dimensions: &GridDimensions) -> String {
+    let mut result = String::new();
+    for y in (0..dimensions.height as i32).rev() {
+        for x in 0..dimensions.width as i32 {
+            let position = Point(x as f32, y as f32);
+            if mobile_rocks.contains_key(&position) {
+                result.push('O');
+                continue;
+            }
+            if fixed_rocks.contains(&position) {
+                result.push('#');
+                continue;
+            }
+            result.push('.');
+        }
+        result.push('\n');
+    }
+    result
+}
\ No newline at end of file
@@ -0,0 +1,58 @@
+use std::time::{Duration, Instant};
+
+use spatial::geometry::{GridDimensions, Point, Ray, Vector};
+use crate::obstacle::Obstacle;
+use spatial::quad_tree::{ArenaQuadTree, Positioned2D, QuadTree, SpatialIndex};
+use crate::ray_tracer::find_energized_tiles;
+
+const BUILD_REPETITIONS: u32 = 100;
+
+// Compares the borrowing quadtree with the arena one: how long they take to build, to answer the
+// queries a simulation makes, and to run the first part of the problem
+pub fn compare_quad_trees(obstacles: &[Obstacle], dimensions: &GridDimensions) {
+    let borrowed_build = time(BUILD_REPETITIONS, || { QuadTree::from_bulk(obstacles, 2); });
+    let arena_build = time(BUILD_REPETITIONS, || { ArenaQuadTree::from_bulk(obstacles.to_vec(), 2); });
+    let borrowed = QuadTree::from_bulk(obstacles, 2);
+    let arena = ArenaQuadTree::from_bulk(obstacles.to_vec(), 2);
+
+    // Every ray leaving an obstacle, as the simulation casts them
+    let rays: Vec<Ray> =
+        obstacles.iter()
+            .flat_map(|obstacle| {
+                [Vector::UP, Vector::DOWN, Vector::LEFT, Vector::RIGHT].map(|direction| Ray(*obstacle.position(), direction))
+            })
+            .collect();
+    let disagreements = rays.iter().filter(|ray| borrowed.intersect(ray, true) != arena.intersect(ray, true)).count();
+    if disagreements > 0 {
+        panic!("The trees disagree on {disagreements} of {} rays", rays.len());
+    }
+    let borrowed_queries = time(1, || query_all(&borrowed, &rays));
+    let arena_queries = time(1, || query_all(&arena, &rays));
+
+    let start = Ray(Point(-1.0, dimensions.height as f32 - 1.0), Vector::RIGHT);
+    let borrowed_run = time(1, || { find_energized_tiles(&start, &borrowed, dimensions); });
+    let arena_run = time(1, || { find_energized_tiles(&start, &arena, dimensions); });
+
+    println!("{:<28}{:>14}{:>14}", "", "Borrowed", "Arena");
+    print_row(&format!("Build (x{BUILD_REPETITIONS})"), borrowed_build, arena_build);
+    print_row(&format!("Queries ({} rays)", rays.len()), borrowed_queries, arena_queries);
+    print_row("Part 1 simulation", borrowed_run, arena_run);
+}
+
+fn query_all(tree: &impl SpatialIndex<Obstacle>, rays: &[Ray]) {
+    for ray in rays {
+        tree.intersect(ray, true);
+    }
+}
+
+fn time<F: FnMut()>(repetitions: u32, mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..repetitions {
+        f();
+    }
+    start.elapsed()
+}
+
+fn print_row(name: &str, borrowed: Duration, arena: Duration) {
+    println!("{name:<28}{:>12.2?}{:>14.2?}", borrowed, arena);
+}
@@ -0,0 +1,261 @@
+use std::collections::HashSet;
+
+use spatial::EPSILON;
+use spatial::geometry::{GridDimensions, Point, Ray, Vector};
+use crate::obstacle::Obstacle;
+use crate::optics::{Mirror, Splitter};
+
+const SVG_TILE_SIZE: f32 = 10.0;
+
+// Optics without snapping: mirrors and splitters are flat surfaces across their tile, with any
+// orientation, and beams keep whatever direction they get. Tile (x, y) covers the square from
+// (x - 0.5, y - 0.5) to (x + 0.5, y + 0.5)
+pub struct Scene {
+    dimensions: GridDimensions,
+    surfaces: Vec<Surface>
+}
+
+// Beam paths from one entry beam, and the tiles they go through
+pub struct Trace {
+    pub segments: Vec<(Point, Point)>,
+    pub energized: HashSet<(i32, i32)>,
+    // Whether the trace stopped at the segment limit rather than when all beams had left the grid
+    pub truncated: bool
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum SurfaceKind { Mirror, Splitter }
+
+// Surface going through the centre of its tile, orthogonal to the normal, and cut at the tile edges
+struct Surface {
+    kind: SurfaceKind,
+    normal: Vector,
+    start: Point,
+    end: Point
+}
+
+impl Scene {
+    pub fn new(obstacles: &[Obstacle], dimensions: &GridDimensions) -> Result<Scene, String> {
+        let surfaces: Vec<Surface> =
+            obstacles.iter()
+                .map(|obstacle| match obstacle {
+                    Obstacle::Mirror(Mirror(center, normal)) => Ok(Surface::new(SurfaceKind::Mirror, center, normal)),
+                    Obstacle::Splitter(Splitter(center, normal)) => Ok(Surface::new(SurfaceKind::Splitter, center, normal)),
+                    other => Err(format!("Only mirrors and splitters can be used in continuous mode, found {other}"))
+                })
+                .collect::<Result<_, _>>()?;
+        Ok(Scene { dimensions: *dimensions, surfaces })
+    }
+
+    // Every surface is checked for each segment, which is plenty fast for puzzle-sized grids. Beams
+    // at arbitrary angles may never repeat exactly, so the number of segments is capped
+    pub fn trace(&self, start: &Ray, max_segments: usize) -> Trace {
+        let mut trace = Trace { segments: Vec::new(), energized: HashSet::new(), truncated: false };
+        // Beams with the surface they leave from, so that they do not hit it again straight away
+        let mut worklist: Vec<(Ray, Option<usize>)> = vec![(Ray(start.0, start.1.normalize()), None)];
+        // Beams leaving a surface, keyed by where they leave it and in which direction
+        let mut seen: HashSet<(usize, [i64; 4])> = HashSet::new();
+        while let Some((ray, source)) = worklist.pop() {
+            if trace.segments.len() >= max_segments {
+                trace.truncated = true;
+                break;
+            }
+            if let Some(source) = source {
+                let key = [ray.0.0, ray.0.1, ray.1.0, ray.1.1].map(|value| (value / EPSILON).round() as i64);
+                if !seen.insert((source, key)) {
+                    continue;
+                }
+            }
+            let Some(exit) = self.exit_distance(&ray) else {
+                continue;
+            };
+            let hit: Option<(usize, f32)> =
+                self.surfaces.iter()
+                    .enumerate()
+                    .filter(|(index, _)| Some(*index) != source)
+                    .filter_map(|(index, surface)| surface.hit_distance(&ray).map(|distance| (index, distance)))
+                    .filter(|(_, distance)| *distance <= exit)
+                    .min_by(|(_, distance1), (_, distance2)| distance1.total_cmp(distance2));
+            let end_distance = hit.map_or(exit, |(_, distance)| distance);
+            let end = ray.0 + ray.1 * end_distance;
+            trace.segments.push((ray.0, end));
+            trace.energized.extend(
+                supercover(&ray.0, &end).into_iter().filter(|&(x, y)| self.contains_tile(x, y)));
+            if let Some((index, _)) = hit {
+                for direction in self.surfaces[index].redirect(&ray.1) {
+                    worklist.push((Ray(end, direction), Some(index)));
+                }
+            }
+        }
+        trace
+    }
+
+    // Text map of the grid with '#' for the energized tiles
+    pub fn coverage_map(&self, trace: &Trace) -> String {
+        let mut map = String::new();
+        for y in (0..self.dimensions.height as i32).rev() {
+            for x in 0..self.dimensions.width as i32 {
+                map.push(if trace.energized.contains(&(x, y)) { '#' } else { '.' });
+            }
+            map.push('\n');
+        }
+        map
+    }
+
+    // Energized tiles, surfaces and beam paths, with the top left corner of the grid at the top left
+    // of the picture
+    pub fn to_svg(&self, trace: &Trace) -> String {
+        let (width, height) = (self.dimensions.width as f32, self.dimensions.height as f32);
+        let to_svg = |Point(x, y): Point| ((x + 0.5) * SVG_TILE_SIZE, (height - 0.5 - y) * SVG_TILE_SIZE);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+            width * SVG_TILE_SIZE, height * SVG_TILE_SIZE);
+        svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"black\"/>\n");
+        let mut energized: Vec<&(i32, i32)> = trace.energized.iter().collect();
+        energized.sort_unstable();
+        for &(x, y) in energized {
+            let (left, top) = to_svg(Point(x as f32 - 0.5, y as f32 + 0.5));
+            svg.push_str(&format!(
+                "<rect x=\"{left}\" y=\"{top}\" width=\"{SVG_TILE_SIZE}\" height=\"{SVG_TILE_SIZE}\" fill=\"#403000\"/>\n"));
+        }
+        for surface in self.surfaces.iter() {
+            let ((x1, y1), (x2, y2)) = (to_svg(surface.start), to_svg(surface.end));
+            let colour = match surface.kind {
+                SurfaceKind::Mirror => "white",
+                SurfaceKind::Splitter => "deepskyblue"
+            };
+            svg.push_str(&format!("<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"{colour}\" stroke-width=\"1.5\"/>\n"));
+        }
+        for &(start, end) in trace.segments.iter() {
+            let ((x1, y1), (x2, y2)) = (to_svg(start), to_svg(end));
+            svg.push_str(&format!("<line x1=\"{x1}\" y1=\"{y1}\" x2=\"{x2}\" y2=\"{y2}\" stroke=\"orange\" stroke-width=\"0.75\"/>\n"));
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn contains_tile(&self, x: i32, y: i32) -> bool {
+        (0..self.dimensions.width as i32).contains(&x) && (0..self.dimensions.height as i32).contains(&y)
+    }
+
+    // Distance along the ray at which it leaves the grid, if it crosses it at all
+    fn exit_distance(&self, ray: &Ray) -> Option<f32> {
+        let limits = [(ray.0.0, ray.1.0, self.dimensions.width as f32), (ray.0.1, ray.1.1, self.dimensions.height as f32)];
+        let (mut entry, mut exit) = (0.0_f32, f32::INFINITY);
+        for (origin, direction, size) in limits {
+            let (low, high) = (-0.5, size - 0.5);
+            if direction.abs() <= EPSILON {
+                if origin < low || origin > high {
+                    return None;
+                }
+                continue;
+            }
+            let (t1, t2) = ((low - origin) / direction, (high - origin) / direction);
+            entry = entry.max(t1.min(t2));
+            exit = exit.min(t1.max(t2));
+        }
+        if exit < entry { None } else { Some(exit) }
+    }
+}
+
+impl Surface {
+    fn new(kind: SurfaceKind, center: &Point, normal: &Vector) -> Surface {
+        let normal = normal.normalize();
+        let along = normal.orthogonal();
+        // Half length of the surface, from the centre to the edge of the tile it reaches first
+        let half_length = 0.5 / along.0.abs().max(along.1.abs());
+        Surface { kind, normal, start: *center + along.inverse() * half_length, end: *center + along * half_length }
+    }
+
+    // Distance along the ray to the surface. Beams running along the surface do not hit it
+    fn hit_distance(&self, ray: &Ray) -> Option<f32> {
+        let along = self.end - self.start;
+        let denominator = cross(&ray.1, &along);
+        if denominator.abs() <= EPSILON {
+            return None;
+        }
+        let to_start = self.start - ray.0;
+        let distance = cross(&to_start, &along) / denominator;
+        let position = cross(&to_start, &ray.1) / denominator;
+        let on_surface = (-EPSILON..=1.0 + EPSILON).contains(&position);
+        if distance > EPSILON && on_surface { Some(distance) } else { None }
+    }
+
+    fn redirect(&self, direction: &Vector) -> Vec<Vector> {
+        match self.kind {
+            SurfaceKind::Mirror => vec![(*direction - self.normal * (2.0 * direction.dot_product(&self.normal))).normalize()],
+            SurfaceKind::Splitter => {
+                let along = self.normal.orthogonal();
+                vec![along, along.inverse()]
+            }
+        }
+    }
+}
+
+// All the tiles the segment goes through, including both neighbours when it goes exactly through a
+// corner. This walks the tile edges like Amanatides and Woo, in coordinates where tiles start at
+// whole numbers
+fn supercover(from: &Point, to: &Point) -> Vec<(i32, i32)> {
+    let (x0, y0) = (from.0 + 0.5, from.1 + 0.5);
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let (mut x, mut y) = (x0.floor() as i32, y0.floor() as i32);
+    let step_x: i32 = if dx > 0.0 { 1 } else { -1 };
+    let step_y: i32 = if dy > 0.0 { 1 } else { -1 };
+    // Fraction of the segment needed to cross a whole tile, and to reach the next edge
+    let axis = |origin: f32, delta: f32| -> (f32, f32) {
+        if delta.abs() <= EPSILON {
+            return (f32::INFINITY, f32::INFINITY);
+        }
+        let next_edge = if delta > 0.0 { origin.floor() + 1.0 } else { origin.floor() };
+        (1.0 / delta.abs(), (next_edge - origin) / delta)
+    };
+    let (t_delta_x, mut t_max_x) = axis(x0, dx);
+    let (t_delta_y, mut t_max_y) = axis(y0, dy);
+    let mut tiles: Vec<(i32, i32)> = vec![(x, y)];
+    while t_max_x.min(t_max_y) < 1.0 - EPSILON {
+        if (t_max_x - t_max_y).abs() <= EPSILON {
+            tiles.push((x + step_x, y));
+            tiles.push((x, y + step_y));
+            x += step_x;
+            y += step_y;
+            t_max_x += t_delta_x;
+            t_max_y += t_delta_y;
+        } else if t_max_x < t_max_y {
+            x += step_x;
+            t_max_x += t_delta_x;
+        } else {
+            y += step_y;
+            t_max_y += t_delta_y;
+        }
+        tiles.push((x, y));
+    }
+    tiles
+}
+
+fn cross(a: &Vector, b: &Vector) -> f32 { a.0 * b.1 - a.1 * b.0 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallel_beams_hitting_one_mirror_are_both_reflected() {
+        // The splitter sends the beam both ways along the bottom row, and two mirrors turn those
+        // into parallel beams 0.4 apart, which both hit the mirror in tile (7, 1)
+        let direction = Vector(1.0, 0.2).normalize();
+        let obstacles = vec![
+            Obstacle::Splitter(Splitter(Point(2.0, 0.0), Vector(0.0, 1.0))),
+            Obstacle::Mirror(Mirror(Point(1.0, 0.0), direction + Vector(1.0, 0.0))),
+            Obstacle::Mirror(Mirror(Point(3.0, 0.0), direction - Vector(1.0, 0.0))),
+            Obstacle::Mirror(Mirror(Point(7.0, 1.0), Vector(-1.0, 0.0)))
+        ];
+        let scene = Scene::new(&obstacles, &GridDimensions { width: 8, height: 4 }).unwrap();
+        let trace = scene.trace(&Ray(Point(2.0, 3.0), Vector(0.0, -1.0)), 100);
+        let reflected: Vec<&(Point, Point)> =
+            trace.segments.iter().filter(|(start, _)| (start.0 - 7.0).abs() <= EPSILON).collect();
+        assert_eq!(reflected.len(), 2);
+        assert!((reflected[0].0.1 - reflected[1].0.1).abs() > 0.3);
+        assert_eq!(trace.segments.len(), 7);
+        assert!(!trace.truncated);
+    }
+}
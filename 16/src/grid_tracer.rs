@@ -0,0 +1,279 @@
+use std::collections::{HashMap, HashSet};
+
+use spatial::geometry::{GridDimensions, Point, Ray};
+use crate::obstacle::Obstacle;
+use crate::optics::{Gate, Mirror, Portal, Rotator, Splitter, Turn};
+use spatial::quad_tree::Positioned2D;
+
+// Beam tracing on integer tile coordinates, with no floating point comparisons. The obstacles of
+// each row and column are kept sorted, so the next one along a beam is a binary search away.
+// Coordinates are the same as for the other tracer: x grows to the right and y grows upwards
+pub struct GridIndex {
+    width: i32,
+    height: i32,
+    elements: HashMap<(i32, i32), Element>,
+    // Sorted x coordinates of the obstacles in each row, indexed by y
+    rows: Vec<Vec<i32>>,
+    // Sorted y coordinates of the obstacles in each column, indexed by x
+    cols: Vec<Vec<i32>>
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Direction { Up, Down, Left, Right }
+
+// Beam leaving the given tile in the given direction
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Beam {
+    pub x: i32,
+    pub y: i32,
+    pub direction: Direction
+}
+
+// Tiles crossed by a beam up to the obstacle it hits, and the beams leaving that obstacle
+pub struct Segment {
+    pub tiles: Vec<(i32, i32)>,
+    pub next: Vec<Beam>
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Element {
+    // '/', going from the bottom left to the top right of the tile
+    RisingMirror,
+    // '\', going from the top left to the bottom right of the tile
+    FallingMirror,
+    // '|'
+    VerticalSplitter,
+    // '-'
+    HorizontalSplitter,
+    // Only lets through the beams going in its direction
+    Gate(Direction),
+    Absorber,
+    Rotator(Turn),
+    Prism,
+    // Beams carry on from the paired tile
+    Portal(i32, i32)
+}
+
+impl GridIndex {
+    pub fn new(obstacles: &[Obstacle], dimensions: &GridDimensions) -> GridIndex {
+        let width = dimensions.width as i32;
+        let height = dimensions.height as i32;
+        let mut elements: HashMap<(i32, i32), Element> = HashMap::new();
+        let mut rows: Vec<Vec<i32>> = vec![Vec::new(); height as usize];
+        let mut cols: Vec<Vec<i32>> = vec![Vec::new(); width as usize];
+        for obstacle in obstacles {
+            let Point(x, y) = *obstacle.position();
+            let (x, y) = (x.round() as i32, y.round() as i32);
+            elements.insert((x, y), Element::from_obstacle(obstacle));
+            rows[y as usize].push(x);
+            cols[x as usize].push(y);
+        }
+        rows.iter_mut().chain(cols.iter_mut()).for_each(|line| line.sort_unstable());
+        GridIndex { width, height, elements, rows, cols }
+    }
+
+    pub fn energized_tiles(&self, start: Beam) -> HashSet<(i32, i32)> {
+        let mut energized: HashSet<(i32, i32)> = HashSet::new();
+        let mut seen: HashSet<Beam> = HashSet::new();
+        let mut worklist: Vec<Beam> = vec![start];
+        while let Some(beam) = worklist.pop() {
+            if !seen.insert(beam) {
+                continue;
+            }
+            let segment = self.segment(&beam);
+            energized.extend(segment.tiles);
+            worklist.extend(segment.next);
+        }
+        energized
+    }
+
+    // Follows the beam up to the next obstacle, or out of the grid
+    pub fn segment(&self, beam: &Beam) -> Segment {
+        // The exit tile can hold an obstacle when the beam starts on it, so only the obstacles
+        // found ahead of the beam redirect it
+        let Some((next_x, next_y)) = self.next_obstacle(beam) else {
+            return Segment { tiles: self.segment_tiles(beam, self.exit_tile(beam)), next: Vec::new() };
+        };
+        let next: Vec<Beam> = self.elements[&(next_x, next_y)].redirect(next_x, next_y, beam.direction);
+        Segment { tiles: self.segment_tiles(beam, (next_x, next_y)), next }
+    }
+
+    // Moves the beam on by a single tile, to the tile it enters and the beams leaving that tile
+    pub fn step(&self, beam: &Beam) -> Segment {
+        let (dx, dy) = beam.direction.step();
+        let (x, y) = (beam.x + dx, beam.y + dy);
+        if !(0..self.width).contains(&x) || !(0..self.height).contains(&y) {
+            return Segment { tiles: Vec::new(), next: Vec::new() };
+        }
+        let next: Vec<Beam> = match self.elements.get(&(x, y)) {
+            None => vec![Beam { x, y, direction: beam.direction }],
+            Some(element) => element.redirect(x, y, beam.direction)
+        };
+        Segment { tiles: vec![(x, y)], next }
+    }
+
+    // Every beam that can leave an obstacle, whether or not any entry beam reaches it
+    pub fn obstacle_beams(&self) -> impl Iterator<Item = Beam> + '_ {
+        self.elements.iter().flat_map(|(&(x, y), element)| element.outputs(x, y))
+    }
+
+    pub fn width(&self) -> i32 { self.width }
+
+    pub fn height(&self) -> i32 { self.height }
+
+    fn next_obstacle(&self, beam: &Beam) -> Option<(i32, i32)> {
+        let Beam { x, y, direction } = *beam;
+        let in_row = (0..self.height).contains(&y);
+        let in_col = (0..self.width).contains(&x);
+        match direction {
+            Direction::Right if in_row => first_after(&self.rows[y as usize], x).map(|x| (x, y)),
+            Direction::Left if in_row => last_before(&self.rows[y as usize], x).map(|x| (x, y)),
+            Direction::Up if in_col => first_after(&self.cols[x as usize], y).map(|y| (x, y)),
+            Direction::Down if in_col => last_before(&self.cols[x as usize], y).map(|y| (x, y)),
+            _ => None
+        }
+    }
+
+    // Last tile inside the grid that the beam crosses when nothing stops it
+    fn exit_tile(&self, beam: &Beam) -> (i32, i32) {
+        match beam.direction {
+            Direction::Right => (self.width - 1, beam.y),
+            Direction::Left => (0, beam.y),
+            Direction::Up => (beam.x, self.height - 1),
+            Direction::Down => (beam.x, 0)
+        }
+    }
+
+    fn segment_tiles(&self, beam: &Beam, end: (i32, i32)) -> Vec<(i32, i32)> {
+        let (dx, dy) = beam.direction.step();
+        let (mut x, mut y) = (beam.x, beam.y);
+        let mut tiles: Vec<(i32, i32)> = Vec::new();
+        loop {
+            if (0..self.width).contains(&x) && (0..self.height).contains(&y) {
+                tiles.push((x, y));
+            }
+            if (x, y) == end {
+                break;
+            }
+            // Beams that start inside the grid and point away from it have already passed their end
+            let passed_end = (end.0 - x) * dx < 0 || (end.1 - y) * dy < 0;
+            if passed_end {
+                break;
+            }
+            x += dx;
+            y += dy;
+        }
+        tiles
+    }
+}
+
+impl Direction {
+    fn clockwise(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up
+        }
+    }
+
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left
+        }
+    }
+
+    fn step(&self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0)
+        }
+    }
+}
+
+impl Beam {
+    pub fn from_ray(ray: &Ray) -> Beam {
+        let direction = match (ray.1.0.round() as i32, ray.1.1.round() as i32) {
+            (0, 1) => Direction::Up,
+            (0, -1) => Direction::Down,
+            (-1, 0) => Direction::Left,
+            (1, 0) => Direction::Right,
+            _ => panic!("Beams can only go along the grid, got {:?}", ray.1)
+        };
+        Beam { x: ray.0.0.round() as i32, y: ray.0.1.round() as i32, direction }
+    }
+}
+
+impl Element {
+    fn from_obstacle(obstacle: &Obstacle) -> Element {
+        match obstacle {
+            Obstacle::Mirror(Mirror(_, ort)) if ort.0 * ort.1 < 0.0 => Element::RisingMirror,
+            Obstacle::Mirror(_) => Element::FallingMirror,
+            Obstacle::Splitter(Splitter(_, ort)) if ort.1 == 0.0 => Element::VerticalSplitter,
+            Obstacle::Splitter(_) => Element::HorizontalSplitter,
+            Obstacle::Gate(Gate(pos, direction)) => Element::Gate(Beam::from_ray(&Ray(*pos, *direction)).direction),
+            Obstacle::Absorber(_) => Element::Absorber,
+            Obstacle::Rotator(Rotator(_, turn)) => Element::Rotator(*turn),
+            Obstacle::Prism(_) => Element::Prism,
+            Obstacle::Portal(Portal(_, Point(x, y))) => Element::Portal(x.round() as i32, y.round() as i32)
+        }
+    }
+
+    // Beams leaving the element on the given tile, whatever the beam that reaches it
+    fn outputs(&self, x: i32, y: i32) -> Vec<Beam> {
+        [Direction::Up, Direction::Down, Direction::Left, Direction::Right].into_iter()
+            .flat_map(|direction| self.redirect(x, y, direction))
+            .fold(Vec::new(), |mut outputs, beam| {
+                if !outputs.contains(&beam) {
+                    outputs.push(beam);
+                }
+                outputs
+            })
+    }
+
+    // Beams leaving the element on the given tile when a beam going in the given direction reaches it
+    fn redirect(&self, x: i32, y: i32, direction: Direction) -> Vec<Beam> {
+        if let Element::Portal(pair_x, pair_y) = *self {
+            return vec![Beam { x: pair_x, y: pair_y, direction }];
+        }
+        self.turn(direction).into_iter()
+            .map(|direction| Beam { x, y, direction })
+            .collect()
+    }
+
+    fn turn(&self, direction: Direction) -> Vec<Direction> {
+        match (self, direction) {
+            (Element::RisingMirror, Direction::Right) => vec![Direction::Up],
+            (Element::RisingMirror, Direction::Up) => vec![Direction::Right],
+            (Element::RisingMirror, Direction::Left) => vec![Direction::Down],
+            (Element::RisingMirror, Direction::Down) => vec![Direction::Left],
+            (Element::FallingMirror, Direction::Right) => vec![Direction::Down],
+            (Element::FallingMirror, Direction::Down) => vec![Direction::Right],
+            (Element::FallingMirror, Direction::Left) => vec![Direction::Up],
+            (Element::FallingMirror, Direction::Up) => vec![Direction::Left],
+            (Element::VerticalSplitter, Direction::Left | Direction::Right) => vec![Direction::Up, Direction::Down],
+            (Element::HorizontalSplitter, Direction::Up | Direction::Down) => vec![Direction::Left, Direction::Right],
+            (Element::Gate(open), direction) if *open != direction => Vec::new(),
+            (Element::Absorber, _) => Vec::new(),
+            (Element::Rotator(Turn::Clockwise), direction) => vec![direction.clockwise()],
+            (Element::Rotator(Turn::CounterClockwise), direction) => vec![direction.clockwise().opposite()],
+            (Element::Prism, direction) => vec![direction, direction.clockwise(), direction.clockwise().opposite()],
+            (_, direction) => vec![direction]
+        }
+    }
+}
+
+fn first_after(sorted: &[i32], value: i32) -> Option<i32> {
+    let index = sorted.partition_point(|&other| other <= value);
+    sorted.get(index).copied()
+}
+
+fn last_before(sorted: &[i32], value: i32) -> Option<i32> {
+    let index = sorted.partition_point(|&other| other < value);
+    index.checked_sub(1).map(|index| sorted[index])
+}
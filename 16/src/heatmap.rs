@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use spatial::geometry::{GridDimensions, Point};
+
+// Side of the square of pixels drawn for each tile
+const PPM_TILE_SIZE: usize = 4;
+
+// Powers of ten of intensity between the brightest tile and the dimmest one that is still told apart
+const HEAT_DECADES: f32 = 3.0;
+
+// Heat of the tiles below that, which stay lit so that they do not look like the unlit ones
+const DIMMEST_HEAT: f32 = 0.05;
+
+// Intensity of each tile from 0 to 1 on a logarithmic scale, where 1 is the brightest tile. Every
+// split halves the energy of the beams, so on a linear scale only the first few beams would show
+pub fn relative_intensities(intensities: &HashMap<Point, f32>) -> HashMap<Point, f32> {
+    let brightest = intensities.values().copied().fold(0.0_f32, f32::max);
+    if brightest <= 0.0 {
+        return HashMap::new();
+    }
+    intensities.iter()
+        .map(|(point, intensity)| (*point, ((intensity / brightest).log10() / HEAT_DECADES + 1.0).max(DIMMEST_HEAT)))
+        .collect()
+}
+
+// Goes from black for unlit tiles, through red and yellow, to white for the brightest ones
+pub fn heat_colour(fraction: f32) -> (u8, u8, u8) {
+    let level = fraction.clamp(0.0, 1.0) * 3.0;
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    (channel(level), channel(level - 1.0), channel(level - 2.0))
+}
+
+// Grid with the text of each tile on a background of its heat colour, with the top row of the grid
+// at the top
+pub fn to_ansi(relative_intensities: &HashMap<Point, f32>, dimensions: &GridDimensions, tile_text: impl Fn(&Point) -> String) -> String {
+    let mut ansi = String::new();
+    for y in (0..dimensions.height).rev() {
+        for x in 0..dimensions.width {
+            let point = Point(x as f32, y as f32);
+            ansi.push_str(&ansi_tile(&tile_text(&point), relative_intensities.get(&point).copied().unwrap_or(0.0)));
+        }
+        ansi.push('\n');
+    }
+    ansi
+}
+
+// Tile text on a background of its heat colour, with dark text on the bright tiles so it stays
+// readable
+fn ansi_tile(text: &str, fraction: f32) -> String {
+    let (red, green, blue) = heat_colour(fraction);
+    let foreground = if fraction > 0.5 { 30 } else { 97 };
+    format!("\x1b[{foreground};48;2;{red};{green};{blue}m{text}\x1b[0m")
+}
+
+// Plain text PPM image of the relative intensities, with the top row of the grid at the top
+pub fn to_ppm(relative_intensities: &HashMap<Point, f32>, dimensions: &GridDimensions) -> String {
+    let mut ppm = format!(
+        "P3\n{} {}\n255\n", dimensions.width * PPM_TILE_SIZE, dimensions.height * PPM_TILE_SIZE);
+    for y in (0..dimensions.height).rev() {
+        let row: Vec<String> =
+            (0..dimensions.width)
+                .map(|x| relative_intensities.get(&Point(x as f32, y as f32)).copied().unwrap_or(0.0))
+                .map(|fraction| {
+                    let (red, green, blue) = heat_colour(fraction);
+                    vec![format!("{red} {green} {blue}"); PPM_TILE_SIZE].join(" ")
+                })
+                .collect();
+        let line = row.join(" ");
+        for _ in 0..PPM_TILE_SIZE {
+            ppm.push_str(&line);
+            ppm.push('\n');
+        }
+    }
+    ppm
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dim_tiles_are_not_drawn_like_unlit_ones() {
+        let intensities = HashMap::from([(Point(0.0, 0.0), 1.0), (Point(1.0, 0.0), 0.1), (Point(2.0, 0.0), 1e-6)]);
+        let relative = relative_intensities(&intensities);
+        assert_eq!(relative[&Point(0.0, 0.0)], 1.0);
+        assert!(relative[&Point(1.0, 0.0)] > relative[&Point(2.0, 0.0)]);
+        assert_eq!(relative[&Point(2.0, 0.0)], DIMMEST_HEAT);
+        assert_ne!(heat_colour(relative[&Point(2.0, 0.0)]), heat_colour(0.0));
+    }
+}
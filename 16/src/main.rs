@@ -1,21 +1,33 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs;
+use std::time::Duration;
 
-
-use geometry::{Point, Vector, Ray};
+use spatial::EPSILON;
+use spatial::geometry::{GridDimensions, Point, Vector, Ray};
+use spatial::quad_tree::{ArenaQuadTree, Positioned2D, QuadTree, SpatialIndex};
+use continuous::{Scene, Trace};
+use grid_tracer::{Beam, GridIndex};
 use obstacle::Obstacle;
-use quad_tree::QuadTree;
-use ray_tracer::find_energized_tiles;
-use quad_tree::Positioned2D;
-use ray_tracer::find_most_energized_configuration;
+use optics::{Gate, Mirror, Rotator, Splitter, Turn};
+use parser::{Kind, Symbols};
+use ranking::RankedRay;
+use ray_tracer::{edge_rays, find_energized_tiles, find_tile_intensities, rank_edge_rays, Attenuation, Intensities};
+use segment_graph::SegmentGraph;
+use simulation::{Frame, Replay};
 
-mod geometry;
-mod quad_tree;
+mod benchmark;
+mod continuous;
+mod grid_tracer;
+mod heatmap;
 mod obstacle;
+mod optics;
+mod parser;
+mod playback;
+mod ranking;
 mod ray_tracer;
-
-const EPSILON: f32 = 1e-4;
+mod segment_graph;
+mod simulation;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -24,84 +36,453 @@ fn main() {
             .expect("should be able to read the file");
     let version: u32 = args[2].parse().expect("Should have a problem version");
     let lines: Vec<&str> = contents.trim().split("\n").collect();
+    let dimensions: GridDimensions =
+        GridDimensions::from_lines(&lines).unwrap_or_else(|error| panic!("Invalid grid in {}: {error}", args[1]));
+    let symbols: Symbols =
+        parse_flag::<String>(&args, "--symbols")
+            .map(|text| text.parse().unwrap_or_else(|error| panic!("Invalid symbols {text}: {error}")))
+            .unwrap_or_default();
     let obstacles: Vec<Obstacle> =
-        lines.iter()
-            .enumerate()
-            .map(|(index, line)| ((lines.len() - 1 - index) as f32, line))
-            .flat_map(|(y, line)| parse_line(line, y))
-            .collect();
-    let top_right_tile = Point(lines.len() as f32 - 1.0, lines[0].len() as f32 - 1.0);
-    let qt: QuadTree<Obstacle> = QuadTree::from_bulk(&obstacles, 2);
+        parser::parse_grid(&lines, &symbols)
+            .unwrap_or_else(|errors| {
+                let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                panic!("Invalid grid in {}:\n{}", args[1], errors.join("\n"))
+            });
 
-    match version {
+    if has_flag(&args, "--benchmark") {
+        benchmark::compare_quad_trees(&obstacles, &dimensions);
+        return;
+    }
+    if has_flag(&args, "--differential") {
+        compare_tracers(&obstacles, version, &dimensions);
+        return;
+    }
+    let ranking_options = RankingOptions {
+        threads: parse_flag(&args, "--threads").unwrap_or_else(ranking::default_threads),
+        csv_path: parse_flag(&args, "--ranking")
+    };
+    let heatmap_options: Option<HeatmapOptions> =
+        if has_flag(&args, "--heatmap") || has_flag(&args, "--ppm") {
+            Some(HeatmapOptions {
+                attenuation: Attenuation {
+                    mirror_factor: parse_flag(&args, "--mirror-factor").unwrap_or(0.9),
+                    min_energy: parse_flag(&args, "--min-energy").unwrap_or(0.01),
+                    max_segments: parse_flag(&args, "--max-segments").unwrap_or(1_000_000)
+                },
+                ansi: has_flag(&args, "--heatmap"),
+                ppm_path: parse_flag(&args, "--ppm")
+            })
+        } else {
+            None
+        };
+    if has_flag(&args, "--animate") || has_flag(&args, "--asciicast") {
+        animate(&args, &lines, &symbols, &obstacles, version, &dimensions, &ranking_options);
+        return;
+    }
+    if has_flag(&args, "--continuous") {
+        solve_continuous(&args, &obstacles, version, &dimensions, &ranking_options);
+        return;
+    }
+    let tracer: String = parse_flag(&args, "--tracer").unwrap_or(String::from("quadtree"));
+    match tracer.as_str() {
+        "quadtree" => {},
+        "grid" => {
+            let grid = GridIndex::new(&obstacles, &dimensions);
+            solve_on_grid(|beam| grid.energized_tiles(beam).len(), version, &dimensions, &ranking_options);
+            return;
+        },
+        "graph" => {
+            let grid = GridIndex::new(&obstacles, &dimensions);
+            let graph = SegmentGraph::new(&grid);
+            println!("Condensed the beams into {} components", graph.component_count());
+            solve_on_grid(|beam| graph.energized_count(beam), version, &dimensions, &ranking_options);
+            return;
+        },
+        _ => panic!("Unknown tracer {tracer}")
+    };
+
+    let load_path: Option<String> = parse_flag(&args, "--load-tree");
+    let save_path: Option<String> = parse_flag(&args, "--save-tree");
+    let default_tree = if load_path.is_some() || save_path.is_some() { "arena" } else { "borrowed" };
+    let tree: String = parse_flag(&args, "--tree").unwrap_or(String::from(default_tree));
+    let check_tree = has_flag(&args, "--check-tree");
+    match tree.as_str() {
+        "borrowed" => {
+            let qt: QuadTree<Obstacle> = QuadTree::from_bulk(&obstacles, 2);
+            if check_tree {
+                qt.check_invariants().unwrap_or_else(|error| panic!("Broken quadtree: {error}"));
+            }
+            solve(&qt, &obstacles, version, &dimensions, &ranking_options, heatmap_options.as_ref(), &symbols);
+        },
+        "arena" => {
+            let qt: ArenaQuadTree<Obstacle> = match load_path {
+                None => ArenaQuadTree::from_bulk(obstacles.clone(), 2),
+                Some(path) => {
+                    let text = fs::read_to_string(&path).expect("should be able to read the tree file");
+                    ArenaQuadTree::from_text(&text).unwrap_or_else(|error| panic!("Invalid tree file {path}: {error}"))
+                }
+            };
+            if let Some(path) = save_path {
+                fs::write(&path, qt.to_text()).expect("should be able to write the tree file");
+            }
+            if check_tree {
+                qt.check_invariants().unwrap_or_else(|error| panic!("Broken quadtree: {error}"));
+            }
+            solve(&qt, &obstacles, version, &dimensions, &ranking_options, heatmap_options.as_ref(), &symbols);
+        },
+        _ => panic!("Unknown tree {tree}")
+    };
+}
+
+fn parse_flag<T: std::str::FromStr>(args: &[String], name: &str) -> Option<T> {
+    args.iter()
+        .position(|arg| arg == name)
+        .map(|index| args.get(index + 1).expect("Flag should have a value"))
+        .map(|value| value.parse().unwrap_or_else(|_| panic!("Invalid value {value} for {name}")))
+}
+
+fn has_flag(args: &[String], name: &str) -> bool { args.iter().any(|arg| arg == name) }
+
+// How the second part evaluates the edge rays, and where it writes their ranking
+struct RankingOptions {
+    threads: usize,
+    csv_path: Option<String>
+}
+
+// How beams lose energy, and where the heat map of the tiles goes
+struct HeatmapOptions {
+    attenuation: Attenuation,
+    ansi: bool,
+    ppm_path: Option<String>
+}
+
+fn solve(
+    qt: &(impl SpatialIndex<Obstacle> + Sync),
+    obstacles: &[Obstacle],
+    version: u32,
+    dimensions: &GridDimensions,
+    ranking_options: &RankingOptions,
+    heatmap_options: Option<&HeatmapOptions>,
+    symbols: &Symbols
+) {
+    let ray: Ray = match version {
         1 => {
             let energized_tiles: HashMap<Point, Vec<Vector>> =
-                find_energized_tiles(&Ray(Point(-1.0, top_right_tile.1), Vector::RIGHT), &qt, &top_right_tile);
+                find_energized_tiles(&part_1_ray(dimensions), qt, dimensions);
             println!("Result is {}", energized_tiles.len());
+            part_1_ray(dimensions)
         },
         2 => {
-            let energized_tiles: HashMap<Point, Vec<Vector>> = find_most_energized_configuration(&qt, &top_right_tile);
-            println!("Result is {}", energized_tiles.len());
+            let ranking: Vec<RankedRay> = rank_edge_rays(qt, dimensions, ranking_options.threads);
+            report_ranking(&ranking, ranking_options);
+            ranking[0].ray
         },
         _ => panic!("Unknown version {version}")
     };
+    if let Some(heatmap_options) = heatmap_options {
+        report_heatmap(&ray, qt, obstacles, dimensions, heatmap_options, symbols);
+    }
 }
 
-fn parse_line(line: &str, y: f32) -> Vec<Obstacle> {
-    line.chars()
-        .enumerate()
-        .map(|(index, c)| (index as f32, c))
-        .filter_map(|(x, c)| {
-            let position = Point(x, y);
-            match c {
-                '.' => None,
-                '/' => Some(Obstacle::Mirror(position, Vector(-1.0, 1.0))),
-                '\\' => Some(Obstacle::Mirror(position, Vector(1.0, 1.0))),
-                '|' => Some(Obstacle::Splitter(position, Vector(1.0, 0.0))),
-                '-' => Some(Obstacle::Splitter(position, Vector(0.0, 1.0))),
-                _ => panic!("Unexpected character {c}")
-            }
-        })
+fn report_heatmap(
+    ray: &Ray,
+    qt: &impl SpatialIndex<Obstacle>,
+    obstacles: &[Obstacle],
+    dimensions: &GridDimensions,
+    options: &HeatmapOptions,
+    symbols: &Symbols
+) {
+    let intensities: Intensities = find_tile_intensities(ray, qt, dimensions, &options.attenuation);
+    if intensities.truncated {
+        println!("Stopped after {} beam segments", options.attenuation.max_segments);
+    }
+    let heat: HashMap<Point, f32> = heatmap::relative_intensities(&intensities.tiles);
+    if options.ansi {
+        let energized_tiles: HashMap<Point, Vec<Vector>> = find_energized_tiles(ray, qt, dimensions);
+        print!("{}", heatmap::to_ansi(&heat, dimensions, |point| tile_character(obstacles, &energized_tiles, symbols, point)));
+    }
+    if let Some(path) = &options.ppm_path {
+        fs::write(path, heatmap::to_ppm(&heat, dimensions)).expect("should be able to write the heat map");
+    }
+    let brightest: Option<(&Point, &f32)> =
+        intensities.tiles.iter().max_by(|(_, intensity1), (_, intensity2)| intensity1.total_cmp(intensity2));
+    if let Some((point, intensity)) = brightest {
+        println!("Brightest tile is {point:?} with an intensity of {intensity}");
+    }
+    println!("{} tiles are lit above the energy threshold", intensities.tiles.len());
+}
+
+fn solve_on_grid(
+    energized_count: impl Fn(Beam) -> usize + Sync,
+    version: u32,
+    dimensions: &GridDimensions,
+    ranking_options: &RankingOptions
+) {
+    match version {
+        1 => println!("Result is {}", energized_count(Beam::from_ray(&part_1_ray(dimensions)))),
+        2 => {
+            let ranking: Vec<RankedRay> =
+                ranking::rank_rays(&edge_rays(dimensions), ranking_options.threads, |ray| energized_count(Beam::from_ray(ray)));
+            report_ranking(&ranking, ranking_options);
+        },
+        _ => panic!("Unknown version {version}")
+    };
+}
+
+// Plays the beams tick by tick in the terminal, or records them for asciinema. The tick length is
+// given in milliseconds
+fn animate(
+    args: &[String],
+    lines: &[&str],
+    symbols: &Symbols,
+    obstacles: &[Obstacle],
+    version: u32,
+    dimensions: &GridDimensions,
+    ranking_options: &RankingOptions
+) {
+    let grid = GridIndex::new(obstacles, dimensions);
+    let start: Beam = match version {
+        1 => Beam::from_ray(&part_1_ray(dimensions)),
+        2 => {
+            let ranking: Vec<RankedRay> =
+                ranking::rank_rays(&edge_rays(dimensions), ranking_options.threads, |ray| grid.energized_tiles(Beam::from_ray(ray)).len());
+            Beam::from_ray(&ranking[0].ray)
+        },
+        _ => panic!("Unknown version {version}")
+    };
+    let frames: Vec<Frame> = simulation::simulate(&grid, start);
+    let mut replay = Replay::new(&frames);
+    let mut screen = |index: usize| {
+        let energized = replay.seek(index);
+        format!("{}{} tiles energized, {} beams\n",
+            simulation::render(&frames[index], energized, lines, symbols), energized.len(), frames[index].beams.len())
+    };
+    let tick = Duration::from_millis(parse_flag(args, "--tick").unwrap_or(100));
+    if let Some(path) = parse_flag::<String>(args, "--asciicast") {
+        fs::write(&path, playback::to_asciicast(frames.len(), &mut screen, tick)).expect("should be able to write the asciicast file");
+    }
+    if has_flag(args, "--animate") {
+        playback::play(frames.len(), &mut screen, tick);
+    }
+    println!("Result is {}", frames.iter().map(|frame| frame.lit.len()).sum::<usize>());
+}
+
+// Traces beams at any angle. A scene file can replace the obstacles of the grid with mirrors and
+// splitters at any orientation, one per line as in the tree files, such as "mirror 3 4 -1 2"
+fn solve_continuous(args: &[String], obstacles: &[Obstacle], version: u32, dimensions: &GridDimensions, ranking_options: &RankingOptions) {
+    let obstacles: Vec<Obstacle> = match parse_flag::<String>(args, "--scene") {
+        None => obstacles.to_vec(),
+        Some(path) => {
+            fs::read_to_string(&path).expect("should be able to read the scene file")
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.trim().parse().unwrap_or_else(|error| panic!("Invalid obstacle in {path}: {error}")))
+                .collect()
+        }
+    };
+    let scene = Scene::new(&obstacles, dimensions).unwrap_or_else(|error| panic!("Invalid scene: {error}"));
+    let max_segments: usize = parse_flag(args, "--max-segments").unwrap_or(100_000);
+    let start: Ray = match version {
+        1 => parse_flag::<String>(args, "--beam").map(|text| parse_ray(&text)).unwrap_or(part_1_ray(dimensions)),
+        2 => {
+            let ranking: Vec<RankedRay> =
+                ranking::rank_rays(&edge_rays(dimensions), ranking_options.threads, |ray| scene.trace(ray, max_segments).energized.len());
+            report_ranking(&ranking, ranking_options);
+            ranking[0].ray
+        },
+        _ => panic!("Unknown version {version}")
+    };
+    let trace: Trace = scene.trace(&start, max_segments);
+    if trace.truncated {
+        println!("Stopped after {max_segments} beam segments");
+    }
+    if has_flag(args, "--coverage") {
+        print!("{}", scene.coverage_map(&trace));
+    }
+    if let Some(path) = parse_flag::<String>(args, "--svg") {
+        fs::write(&path, scene.to_svg(&trace)).expect("should be able to write the SVG file");
+    }
+    // The ranking already reported the result of the best ray
+    if version == 1 {
+        println!("Result is {}", trace.energized.len());
+    }
+}
+
+// Ray written as its origin and direction, such as "-1 9 1 0.25"
+fn parse_ray(text: &str) -> Ray {
+    let numbers: Vec<f32> =
+        text.split_whitespace()
+            .map(|field| field.parse().unwrap_or_else(|_| panic!("Invalid number {field} in beam {text}")))
+            .collect();
+    match numbers.as_slice() {
+        &[x, y, dx, dy] => Ray(Point(x, y), Vector(dx, dy)),
+        _ => panic!("Expected the beam as x y dx dy, got {text}")
+    }
+}
+
+fn report_ranking(ranking: &[RankedRay], ranking_options: &RankingOptions) {
+    println!("Best Ray is {:?}", ranking[0].ray);
+    println!("Result is {}", ranking[0].energized);
+    if let Some(path) = &ranking_options.csv_path {
+        fs::write(path, ranking::to_csv(ranking)).expect("should be able to write the ranking file");
+    }
+}
+
+fn part_1_ray(dimensions: &GridDimensions) -> Ray { Ray(Point(-1.0, dimensions.height as f32 - 1.0), Vector::RIGHT) }
+
+// Runs every tracer on the rays of the given version, and reports every ray where the energized
+// tiles differ from the quadtree ones
+fn compare_tracers(obstacles: &[Obstacle], version: u32, dimensions: &GridDimensions) {
+    let rays: Vec<Ray> = start_rays(version, dimensions);
+    let mut mismatches: usize = 0;
+    for (ray, energized) in rays.iter().zip(trace_with_every_tracer(obstacles, dimensions, &rays)) {
+        let (_, from_quad_tree) = &energized[0];
+        let differing: Vec<&TracerTiles> =
+            energized[1..].iter().filter(|(_, tiles)| tiles != from_quad_tree).collect();
+        if !differing.is_empty() {
+            mismatches += 1;
+        }
+        for (tracer, tiles) in differing {
+            println!("{:?}: {} tiles with the quadtree, {} with the {tracer}, {} in only one of them",
+                ray, from_quad_tree.len(), tiles.len(), from_quad_tree.symmetric_difference(tiles).count());
+        }
+    }
+    println!("{mismatches} of {} rays differ", rays.len());
+}
+
+fn start_rays(version: u32, dimensions: &GridDimensions) -> Vec<Ray> {
+    match version {
+        1 => vec![part_1_ray(dimensions)],
+        2 => edge_rays(dimensions),
+        _ => panic!("Unknown version {version}")
+    }
+}
+
+// Name of a tracer, with the tiles it energized
+type TracerTiles = (&'static str, HashSet<(i32, i32)>);
+
+// Energized tiles of each ray with every tracer, starting with the quadtree
+fn trace_with_every_tracer(obstacles: &[Obstacle], dimensions: &GridDimensions, rays: &[Ray]) -> Vec<[TracerTiles; 4]> {
+    let qt: QuadTree<Obstacle> = QuadTree::from_bulk(obstacles, 2);
+    let arena_qt: ArenaQuadTree<Obstacle> = ArenaQuadTree::from_bulk(obstacles.to_vec(), 2);
+    let grid = GridIndex::new(obstacles, dimensions);
+    let graph = SegmentGraph::new(&grid);
+    let to_tiles = |tiles: HashMap<Point, Vec<Vector>>| -> HashSet<(i32, i32)> {
+        tiles.keys().map(|Point(x, y)| (x.round() as i32, y.round() as i32)).collect()
+    };
+    rays.iter()
+        .map(|ray| [
+            ("quadtree", to_tiles(find_energized_tiles(ray, &qt, dimensions))),
+            ("arena quadtree", to_tiles(find_energized_tiles(ray, &arena_qt, dimensions))),
+            ("grid", grid.energized_tiles(Beam::from_ray(ray))),
+            ("segment graph", graph.energized_tiles(Beam::from_ray(ray)))
+        ])
         .collect()
 }
 
-#[allow(dead_code)]
-fn to_string(items: &Vec<Obstacle>, top_right_tile: &Point, energized_tiles: &HashMap<Point, Vec<Vector>>) -> String {
-    let mut result = String::new();
-    for y in (0..=top_right_tile.1 as i32).rev() {
-        for x in 0..=top_right_tile.0 as i32 {
-            let x = x as f32;
-            let y = y as f32;
-            let item = match items.iter().find(|item| *item.position() == Point(x, y)) {
-                None => {
-                    match energized_tiles.get(&Point(x, y)) {
-                        None => {
-                            result.push('.');
-                            continue;
-                        }
-                        Some(directions) => {
-                            let character =
-                                if directions.len() > 1 { directions.len().to_string() }
-                                else if directions[0] == Vector::UP { String::from("^") }
-                                else if directions[0] == Vector::DOWN { String::from("v") }
-                                else if directions[0] == Vector::RIGHT { String::from(">") }
-                                else if directions[0] == Vector::LEFT { String::from("<") }
-                                else { panic!("Unknown directino vector") };
-                            result.push_str(&character);
-                            continue;
-                        }
-                    }
-                }
-                Some(item) => item
+// Obstacle on the tile, or an arrow when an energized tile is crossed in a single direction, or
+// the number of directions. The arrows are not the ASCII ones, which are the default gates
+fn tile_character(items: &[Obstacle], energized_tiles: &HashMap<Point, Vec<Vector>>, symbols: &Symbols, point: &Point) -> String {
+    let item = match items.iter().find(|item| item.position() == point) {
+        None => {
+            return match energized_tiles.get(point) {
+                None => symbols.character(Kind::Empty).to_string(),
+                Some(directions) =>
+                    if directions.len() > 1 { directions.len().to_string() }
+                    else if directions[0] == Vector::UP { String::from("↑") }
+                    else if directions[0] == Vector::DOWN { String::from("↓") }
+                    else if directions[0] == Vector::RIGHT { String::from("→") }
+                    else if directions[0] == Vector::LEFT { String::from("←") }
+                    else { panic!("Unknown direction vector") }
             };
-            match item {
-                Obstacle::Splitter(_, ort) =>
-                    result.push(if ort.dot_product(&Vector::UP).abs() <= EPSILON { '|' } else { '-' } ),
-                Obstacle::Mirror(_, ort) =>
-                    result.push(if ort.dot_product(&Vector(1.0,1.0)).abs() <= EPSILON { '/' } else { '\\' } )
+        }
+        Some(item) => item
+    };
+    // Portals lose the character that paired them, so they all show as a star
+    let kind = match item {
+        Obstacle::Splitter(Splitter(_, ort)) =>
+            if ort.dot_product(&Vector::UP).abs() <= EPSILON { Kind::VerticalSplitter } else { Kind::HorizontalSplitter },
+        Obstacle::Mirror(Mirror(_, ort)) =>
+            if ort.dot_product(&Vector(1.0,1.0)).abs() <= EPSILON { Kind::RisingMirror } else { Kind::FallingMirror },
+        Obstacle::Gate(Gate(_, direction)) =>
+            if direction.1 > EPSILON { Kind::GateUp }
+            else if direction.1 < -EPSILON { Kind::GateDown }
+            else if direction.0 < 0.0 { Kind::GateLeft }
+            else { Kind::GateRight },
+        Obstacle::Absorber(_) => Kind::Absorber,
+        Obstacle::Rotator(Rotator(_, Turn::Clockwise)) => Kind::ClockwiseRotator,
+        Obstacle::Rotator(Rotator(_, Turn::CounterClockwise)) => Kind::CounterClockwiseRotator,
+        Obstacle::Prism(_) => Kind::Prism,
+        Obstacle::Portal(_) => return String::from("*")
+    };
+    symbols.character(kind).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_tracers_agree(path: &str) {
+        let contents = fs::read_to_string(format!("{}/{path}", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        let lines: Vec<&str> = contents.trim().split("\n").collect();
+        let dimensions = GridDimensions::from_lines(&lines).unwrap();
+        let obstacles: Vec<Obstacle> = parser::parse_grid(&lines, &Symbols::default()).unwrap();
+        for version in [1, 2] {
+            let rays: Vec<Ray> = start_rays(version, &dimensions);
+            for (ray, energized) in rays.iter().zip(trace_with_every_tracer(&obstacles, &dimensions, &rays)) {
+                let (_, from_quad_tree) = &energized[0];
+                assert!(!from_quad_tree.is_empty(), "{path}: nothing energized by {ray:?}");
+                for (tracer, tiles) in energized[1..].iter() {
+                    assert_eq!(tiles, from_quad_tree, "{path}: the {tracer} differs from the quadtree on {ray:?}");
+                }
             }
         }
-        result.push('\n');
     }
-    return result;
-}
\ No newline at end of file
+
+    fn read_lines(path: &str) -> Vec<String> {
+        let contents = fs::read_to_string(format!("{}/{path}", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        contents.trim().split('\n').map(String::from).collect()
+    }
+
+    #[test]
+    fn ragged_grids_are_rejected() {
+        let lines = read_lines("test_input_ragged.txt");
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        assert_eq!(
+            GridDimensions::from_lines(&lines),
+            Err(String::from("Line 4 is 9 tiles wide, but line 1 is 10 tiles wide")));
+    }
+
+    #[test]
+    fn rectangular_grids_are_solved() {
+        let lines = read_lines("test_input_rectangular.txt");
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let dimensions = GridDimensions::from_lines(&lines).unwrap();
+        assert_eq!(dimensions, GridDimensions { width: 14, height: 6 });
+        assert_eq!(part_1_ray(&dimensions), Ray(Point(-1.0, 5.0), Vector::RIGHT));
+        let obstacles: Vec<Obstacle> = parser::parse_grid(&lines, &Symbols::default()).unwrap();
+        let qt: QuadTree<Obstacle> = QuadTree::from_bulk(&obstacles, 2);
+        assert_eq!(find_energized_tiles(&part_1_ray(&dimensions), &qt, &dimensions).len(), 7);
+        assert_eq!(rank_edge_rays(&qt, &dimensions, 1)[0].energized, 27);
+        let grid = GridIndex::new(&obstacles, &dimensions);
+        assert_eq!(grid.energized_tiles(Beam::from_ray(&part_1_ray(&dimensions))).len(), 7);
+    }
+
+    #[test]
+    fn tracers_agree_on_the_sample() { assert_tracers_agree("test_input.txt") }
+
+    #[test]
+    fn tracers_agree_on_the_puzzle_input() { assert_tracers_agree("input.txt") }
+
+    #[test]
+    fn tracers_agree_on_the_small_samples() {
+        assert_tracers_agree("test_input2.txt");
+        assert_tracers_agree("test_input3.txt");
+    }
+
+    #[test]
+    fn tracers_agree_on_every_kind_of_obstacle() { assert_tracers_agree("test_input_optics.txt") }
+
+    #[test]
+    fn tracers_agree_on_a_rectangular_grid() { assert_tracers_agree("test_input_rectangular.txt") }
+}
@@ -1,69 +1,95 @@
-use crate::EPSILON;
-use crate::geometry::{Point, Ray, Vector};
-use crate::quad_tree::Positioned2D;
+use std::fmt;
+use std::str::FromStr;
 
-// Obstacle implementation based on position and orthogonal vector
+use spatial::geometry::{Point, Ray, Vector};
+use crate::optics::{Absorber, Gate, Mirror, OpticalElement, Portal, Prism, Rotator, Splitter, Turn};
+use spatial::quad_tree::Positioned2D;
+
+// Every kind of optical element, so that they can be stored by value in the spatial indexes
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Obstacle {
-    Splitter(Point, Vector),
-    Mirror(Point, Vector)
+    Splitter(Splitter),
+    Mirror(Mirror),
+    Gate(Gate),
+    Absorber(Absorber),
+    Rotator(Rotator),
+    Prism(Prism),
+    Portal(Portal)
 }
 
 impl Obstacle {
-    pub fn reflect(&self, ray: &Ray) -> Vec<Ray> {
+    pub fn element(&self) -> &dyn OpticalElement {
         match self {
-            Obstacle::Splitter(pos, ort) => Self::reflect_on_splitter(pos, ort, ray),
-            Obstacle::Mirror(pos, ort) => Self::reflect_on_mirror(pos, ort, ray)
+            Obstacle::Splitter(splitter) => splitter,
+            Obstacle::Mirror(mirror) => mirror,
+            Obstacle::Gate(gate) => gate,
+            Obstacle::Absorber(absorber) => absorber,
+            Obstacle::Rotator(rotator) => rotator,
+            Obstacle::Prism(prism) => prism,
+            Obstacle::Portal(portal) => portal
         }
     }
+}
 
-    fn reflect_on_splitter(pos: &Point, ort: &Vector, ray: &Ray) -> Vec<Ray> {
-        let dot_product = ray.1.dot_product(ort);
-        let angle_cos = dot_product / (ort.magnitude() * ray.1.magnitude());
-        let is_parallel = angle_cos.abs() <= EPSILON;
-        let is_orthogonal = (angle_cos.abs() - 1.0).abs() <= EPSILON;
-        if is_parallel {
-            return vec![Ray(*pos, ray.1)];
-        }
-        if is_orthogonal {
-            let new_direction = ort.orthogonal();
-            return vec![Ray(*pos, new_direction), Ray(*pos, new_direction.inverse())];
-        }
-        panic!("Hit a splitter from a weird angle!");
-    }
+impl OpticalElement for Obstacle {
+    fn redirect(&self, ray: &Ray) -> Vec<Ray> { self.element().redirect(ray) }
+}
 
-    fn reflect_on_mirror(pos: &Point, ort: &Vector, ray: &Ray) -> Vec<Ray> {
-        let dot_product = ray.1.dot_product(ort);
-        let angle_cos = dot_product / (ort.magnitude() * ray.1.magnitude());
-        let is_parallel = angle_cos.abs() <= EPSILON;
-        let is_orthogonal = (angle_cos.abs() - 1.0).abs() <= EPSILON;
-        if is_parallel {
-            panic!("Hit a mirror from a weird angle!");
-        }
-        if is_orthogonal {
-            // Hitting the mirror head-on at an orthogonal angle
-            return vec![Ray(*pos, ray.1.inverse())];
+impl Positioned2D for Obstacle {
+    fn position(&self) -> &Point { self.element().position() }
+}
+
+// Written as the kind followed by the position and the settings of the element, such as
+// "mirror 3 4 -1 1" for a mirror with its orthogonal vector, or "portal 1 2 5 6" for a portal with
+// its pair
+impl fmt::Display for Obstacle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Point(x, y) = *self.element().position();
+        match self {
+            Obstacle::Splitter(Splitter(_, Vector(ort_x, ort_y))) => write!(f, "splitter {x} {y} {ort_x} {ort_y}"),
+            Obstacle::Mirror(Mirror(_, Vector(ort_x, ort_y))) => write!(f, "mirror {x} {y} {ort_x} {ort_y}"),
+            Obstacle::Gate(Gate(_, Vector(dx, dy))) => write!(f, "gate {x} {y} {dx} {dy}"),
+            Obstacle::Absorber(_) => write!(f, "absorber {x} {y}"),
+            Obstacle::Rotator(Rotator(_, Turn::Clockwise)) => write!(f, "rotator {x} {y} clockwise"),
+            Obstacle::Rotator(Rotator(_, Turn::CounterClockwise)) => write!(f, "rotator {x} {y} counterclockwise"),
+            Obstacle::Prism(_) => write!(f, "prism {x} {y}"),
+            Obstacle::Portal(Portal(_, Point(pair_x, pair_y))) => write!(f, "portal {x} {y} {pair_x} {pair_y}")
         }
-        let normal_same_side: Vector = if angle_cos < 0.0 { ort.normalize() } else { ort.inverse().normalize() };
-        let new_direction = ray.1 - normal_same_side * (2.0 * ray.1.dot_product(&normal_same_side));
-        let clamped_new_direction =
-            [Vector::UP, Vector::DOWN, Vector::LEFT, Vector::RIGHT]
-                .iter()
-                .max_by(|vec1, vec2| {
-                    let dot_product1 = vec1.dot_product(&new_direction);
-                    let dot_product2 = vec2.dot_product(&new_direction);
-                    dot_product1.partial_cmp(&dot_product2).unwrap()
-                })
-                .unwrap();
-        vec![Ray(pos.clone(), clamped_new_direction.clone())]
     }
 }
 
-impl Positioned2D for Obstacle {
-    fn position(&self) -> &Point {
-        match self {
-            Obstacle::Splitter(pos, _) => pos,
-            Obstacle::Mirror(pos, _, ) => pos
+impl FromStr for Obstacle {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Obstacle, String> {
+        let fields: Vec<&str> = text.split(' ').collect();
+        let numbers = |count: usize| -> Result<Vec<f32>, String> {
+            if fields.len() != count + 1 {
+                return Err(format!("Expected {} and {count} numbers in {text}", fields[0]));
+            }
+            fields[1..].iter().map(|field| parse_number(field)).collect()
+        };
+        match fields[0] {
+            "splitter" => numbers(4).map(|n| Obstacle::Splitter(Splitter(Point(n[0], n[1]), Vector(n[2], n[3])))),
+            "mirror" => numbers(4).map(|n| Obstacle::Mirror(Mirror(Point(n[0], n[1]), Vector(n[2], n[3])))),
+            "gate" => numbers(4).map(|n| Obstacle::Gate(Gate(Point(n[0], n[1]), Vector(n[2], n[3])))),
+            "absorber" => numbers(2).map(|n| Obstacle::Absorber(Absorber(Point(n[0], n[1])))),
+            "prism" => numbers(2).map(|n| Obstacle::Prism(Prism(Point(n[0], n[1])))),
+            "portal" => numbers(4).map(|n| Obstacle::Portal(Portal(Point(n[0], n[1]), Point(n[2], n[3])))),
+            "rotator" => {
+                let turn = match fields.get(3) {
+                    Some(&"clockwise") => Turn::Clockwise,
+                    Some(&"counterclockwise") => Turn::CounterClockwise,
+                    _ => return Err(format!("Expected rotator, 2 numbers and clockwise or counterclockwise in {text}"))
+                };
+                if fields.len() != 4 {
+                    return Err(format!("Expected rotator, 2 numbers and clockwise or counterclockwise in {text}"));
+                }
+                Ok(Obstacle::Rotator(Rotator(Point(parse_number(fields[1])?, parse_number(fields[2])?), turn)))
+            },
+            kind => Err(format!("Unknown obstacle kind {kind}"))
         }
     }
-}
\ No newline at end of file
+}
+
+fn parse_number(field: &str) -> Result<f32, String> { field.parse().map_err(|_| format!("Invalid number {field}")) }
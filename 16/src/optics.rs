@@ -0,0 +1,185 @@
+use spatial::EPSILON;
+use spatial::geometry::{Point, Ray, Vector};
+use spatial::quad_tree::Positioned2D;
+
+// Element that beams can run into. Given a beam reaching its position, it tells which beams leave
+// it. Adding an element means implementing this trait, and giving it a variant in Obstacle so the
+// spatial indexes can store it
+pub trait OpticalElement: Positioned2D {
+    fn redirect(&self, ray: &Ray) -> Vec<Ray>;
+}
+
+// Lets beams parallel to it through, and splits the other ones in two. The vector is orthogonal to
+// the splitter
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Splitter(pub Point, pub Vector);
+
+// Reflects beams, with the vector orthogonal to the mirror
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mirror(pub Point, pub Vector);
+
+// Lets beams going in the given direction through and stops all the others
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Gate(pub Point, pub Vector);
+
+// Stops every beam
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Absorber(pub Point);
+
+// Turns beams a quarter turn
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rotator(pub Point, pub Turn);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Turn { Clockwise, CounterClockwise }
+
+// Lets beams through, and also sends beams to both sides
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Prism(pub Point);
+
+// Sends beams on from the paired portal, in the same direction
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Portal(pub Point, pub Point);
+
+impl Positioned2D for Splitter {
+    fn position(&self) -> &Point { &self.0 }
+}
+
+impl OpticalElement for Splitter {
+    fn redirect(&self, ray: &Ray) -> Vec<Ray> {
+        let Splitter(pos, ort) = self;
+        let angle_cos = ray.1.dot_product(ort) / (ort.magnitude() * ray.1.magnitude());
+        if angle_cos.abs() <= EPSILON {
+            return vec![Ray(*pos, ray.1)];
+        }
+        // Beams hitting it from any other angle leave along it, both ways
+        let new_direction = ort.orthogonal();
+        vec![Ray(*pos, new_direction), Ray(*pos, new_direction.inverse())]
+    }
+}
+
+impl Positioned2D for Mirror {
+    fn position(&self) -> &Point { &self.0 }
+}
+
+impl OpticalElement for Mirror {
+    fn redirect(&self, ray: &Ray) -> Vec<Ray> {
+        let Mirror(pos, ort) = self;
+        let normal = ort.normalize();
+        let dot_product = ray.1.dot_product(&normal);
+        // Beams running along the mirror only graze it
+        if (dot_product / ray.1.magnitude()).abs() <= EPSILON {
+            return vec![Ray(*pos, ray.1)];
+        }
+        let new_direction = ray.1 - normal * (2.0 * dot_product);
+        vec![Ray(*pos, snap_to_whole(new_direction))]
+    }
+}
+
+impl Positioned2D for Gate {
+    fn position(&self) -> &Point { &self.0 }
+}
+
+impl OpticalElement for Gate {
+    fn redirect(&self, ray: &Ray) -> Vec<Ray> {
+        let Gate(pos, direction) = self;
+        let angle_cos = ray.1.dot_product(direction) / (ray.1.magnitude() * direction.magnitude());
+        if (angle_cos - 1.0).abs() <= EPSILON { vec![Ray(*pos, ray.1)] } else { Vec::new() }
+    }
+}
+
+impl Positioned2D for Absorber {
+    fn position(&self) -> &Point { &self.0 }
+}
+
+impl OpticalElement for Absorber {
+    fn redirect(&self, _: &Ray) -> Vec<Ray> { Vec::new() }
+}
+
+impl Positioned2D for Rotator {
+    fn position(&self) -> &Point { &self.0 }
+}
+
+impl OpticalElement for Rotator {
+    fn redirect(&self, ray: &Ray) -> Vec<Ray> {
+        let Rotator(pos, turn) = self;
+        match turn {
+            Turn::Clockwise => vec![Ray(*pos, ray.1.orthogonal())],
+            Turn::CounterClockwise => vec![Ray(*pos, ray.1.orthogonal().inverse())]
+        }
+    }
+}
+
+impl Positioned2D for Prism {
+    fn position(&self) -> &Point { &self.0 }
+}
+
+impl OpticalElement for Prism {
+    fn redirect(&self, ray: &Ray) -> Vec<Ray> {
+        let side = ray.1.orthogonal();
+        vec![Ray(self.0, ray.1), Ray(self.0, side), Ray(self.0, side.inverse())]
+    }
+}
+
+impl Positioned2D for Portal {
+    fn position(&self) -> &Point { &self.0 }
+}
+
+impl OpticalElement for Portal {
+    fn redirect(&self, ray: &Ray) -> Vec<Ray> { vec![Ray(self.1, ray.1)] }
+}
+
+// Rounds the components that are within rounding errors of a whole number, so that beams reflected
+// onto an axis compare equal to the axis directions
+fn snap_to_whole(vector: Vector) -> Vector {
+    let snap = |value: f32| if (value - value.round()).abs() <= EPSILON { value.round() } else { value };
+    Vector(snap(vector.0), snap(vector.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directions(rays: Vec<Ray>) -> Vec<Vector> { rays.into_iter().map(|ray| ray.1).collect() }
+
+    #[test]
+    fn mirrors_reflect_beams_onto_the_axes_exactly() {
+        let rising = Mirror(Point(0.0, 0.0), Vector(-1.0, 1.0));
+        assert_eq!(directions(rising.redirect(&Ray(Point(-1.0, 0.0), Vector::RIGHT))), vec![Vector::UP]);
+        assert_eq!(directions(rising.redirect(&Ray(Point(0.0, 1.0), Vector::DOWN))), vec![Vector::LEFT]);
+        let falling = Mirror(Point(0.0, 0.0), Vector(1.0, 1.0));
+        assert_eq!(directions(falling.redirect(&Ray(Point(-1.0, 0.0), Vector::RIGHT))), vec![Vector::DOWN]);
+    }
+
+    #[test]
+    fn mirrors_reflect_off_axis_beams_without_snapping_them() {
+        let mirror = Mirror(Point(0.0, 0.0), Vector(0.0, 1.0));
+        let reflected = directions(mirror.redirect(&Ray(Point(-2.0, 1.0), Vector(2.0, -1.0))));
+        assert_eq!(reflected, vec![Vector(2.0, 1.0)]);
+        let tilted = Mirror(Point(0.0, 0.0), Vector(1.0, 2.0));
+        let reflected = directions(tilted.redirect(&Ray(Point(-1.0, 0.0), Vector::RIGHT)));
+        assert!((reflected[0].0 - 0.6).abs() <= EPSILON && (reflected[0].1 + 0.8).abs() <= EPSILON);
+    }
+
+    #[test]
+    fn mirrors_let_parallel_beams_through() {
+        let mirror = Mirror(Point(0.0, 0.0), Vector(1.0, 1.0));
+        let ray = Ray(Point(-1.0, 1.0), Vector(1.0, -1.0));
+        assert_eq!(mirror.redirect(&ray), vec![Ray(Point(0.0, 0.0), Vector(1.0, -1.0))]);
+    }
+
+    #[test]
+    fn splitters_split_off_axis_beams_along_them() {
+        let splitter = Splitter(Point(0.0, 0.0), Vector(1.0, 0.0));
+        let split = directions(splitter.redirect(&Ray(Point(-2.0, -1.0), Vector(2.0, 1.0))));
+        assert_eq!(split, vec![Vector::DOWN, Vector::UP]);
+    }
+
+    #[test]
+    fn splitters_let_parallel_beams_through() {
+        let splitter = Splitter(Point(0.0, 0.0), Vector(1.0, 0.0));
+        assert_eq!(directions(splitter.redirect(&Ray(Point(0.0, -1.0), Vector::UP))), vec![Vector::UP]);
+        let tilted = Splitter(Point(0.0, 0.0), Vector(1.0, 1.0));
+        assert_eq!(directions(tilted.redirect(&Ray(Point(-1.0, 1.0), Vector(1.0, -1.0)))), vec![Vector(1.0, -1.0)]);
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use spatial::geometry::{Point, Vector};
+use crate::obstacle::Obstacle;
+use crate::optics::{Absorber, Gate, Mirror, Portal, Prism, Rotator, Splitter, Turn};
+
+// What a character of the puzzle stands for
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Empty,
+    RisingMirror,
+    FallingMirror,
+    VerticalSplitter,
+    HorizontalSplitter,
+    GateUp,
+    GateDown,
+    GateLeft,
+    GateRight,
+    Absorber,
+    ClockwiseRotator,
+    CounterClockwiseRotator,
+    Prism,
+    // Portals are paired by character, so each portal character must appear exactly twice
+    Portal
+}
+
+// Name of each kind on the command line, with its default characters
+const KINDS: [(&str, Kind, &str); 14] = [
+    ("empty", Kind::Empty, "."),
+    ("rising-mirror", Kind::RisingMirror, "/"),
+    ("falling-mirror", Kind::FallingMirror, "\\"),
+    ("vertical-splitter", Kind::VerticalSplitter, "|"),
+    ("horizontal-splitter", Kind::HorizontalSplitter, "-"),
+    ("gate-up", Kind::GateUp, "^"),
+    ("gate-down", Kind::GateDown, "v"),
+    ("gate-left", Kind::GateLeft, "<"),
+    ("gate-right", Kind::GateRight, ">"),
+    ("absorber", Kind::Absorber, "#"),
+    ("clockwise-rotator", Kind::ClockwiseRotator, "R"),
+    ("counterclockwise-rotator", Kind::CounterClockwiseRotator, "L"),
+    ("prism", Kind::Prism, "+"),
+    ("portal", Kind::Portal, "0123456789")
+];
+
+// Characters of each kind of tile
+pub struct Symbols {
+    kinds: HashMap<char, Kind>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String
+}
+
+impl Kind {
+    fn obstacle(&self, position: Point) -> Option<Obstacle> {
+        match self {
+            Kind::Empty | Kind::Portal => None,
+            Kind::RisingMirror => Some(Obstacle::Mirror(Mirror(position, Vector(-1.0, 1.0)))),
+            Kind::FallingMirror => Some(Obstacle::Mirror(Mirror(position, Vector(1.0, 1.0)))),
+            Kind::VerticalSplitter => Some(Obstacle::Splitter(Splitter(position, Vector(1.0, 0.0)))),
+            Kind::HorizontalSplitter => Some(Obstacle::Splitter(Splitter(position, Vector(0.0, 1.0)))),
+            Kind::GateUp => Some(Obstacle::Gate(Gate(position, Vector::UP))),
+            Kind::GateDown => Some(Obstacle::Gate(Gate(position, Vector::DOWN))),
+            Kind::GateLeft => Some(Obstacle::Gate(Gate(position, Vector::LEFT))),
+            Kind::GateRight => Some(Obstacle::Gate(Gate(position, Vector::RIGHT))),
+            Kind::Absorber => Some(Obstacle::Absorber(Absorber(position))),
+            Kind::ClockwiseRotator => Some(Obstacle::Rotator(Rotator(position, Turn::Clockwise))),
+            Kind::CounterClockwiseRotator => Some(Obstacle::Rotator(Rotator(position, Turn::CounterClockwise))),
+            Kind::Prism => Some(Obstacle::Prism(Prism(position)))
+        }
+    }
+}
+
+impl Symbols {
+    pub fn kind(&self, c: char) -> Option<Kind> { self.kinds.get(&c).copied() }
+
+    // Smallest character of the kind, or its default one when it was given no characters
+    pub fn character(&self, kind: Kind) -> char {
+        self.kinds.iter()
+            .filter(|(_, other)| **other == kind)
+            .map(|(c, _)| *c)
+            .min()
+            .unwrap_or_else(|| {
+                let (_, _, defaults) = KINDS.iter().find(|(_, other, _)| *other == kind).expect("Every kind has defaults");
+                defaults.chars().next().expect("Every kind has a default character")
+            })
+    }
+}
+
+impl Default for Symbols {
+    fn default() -> Symbols {
+        let kinds: HashMap<char, Kind> =
+            KINDS.iter()
+                .flat_map(|(_, kind, symbols)| symbols.chars().map(move |c| (c, *kind)))
+                .collect();
+        Symbols { kinds }
+    }
+}
+
+// Changes the characters of some kinds, written as "prism=*,portal=ab". The kinds that are not
+// given keep their default characters, and no character can stand for two kinds
+impl FromStr for Symbols {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Symbols, String> {
+        let mut characters: Vec<(&str, Kind, String)> =
+            KINDS.iter().map(|(name, kind, symbols)| (*name, *kind, symbols.to_string())).collect();
+        for assignment in text.split(',') {
+            let (name, symbols) =
+                assignment.split_once('=')
+                    .ok_or_else(|| format!("Expected a kind and its characters in {assignment}"))?;
+            let (_, _, kind_symbols) =
+                characters.iter_mut()
+                    .find(|(kind_name, _, _)| *kind_name == name)
+                    .ok_or_else(|| format!("Unknown kind {name}"))?;
+            *kind_symbols = symbols.to_string();
+        }
+        let mut kinds: HashMap<char, Kind> = HashMap::new();
+        let mut names: HashMap<char, &str> = HashMap::new();
+        for (name, kind, symbols) in characters.iter() {
+            for c in symbols.chars() {
+                if let Some(other) = names.insert(c, name).filter(|other| other != name) {
+                    return Err(format!("Character {c} is used by both {other} and {name}"));
+                }
+                kinds.insert(c, *kind);
+            }
+        }
+        Ok(Symbols { kinds })
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+// Reads every line, and reports all the characters that could not be read rather than the first one
+pub fn parse_grid(lines: &[&str], symbols: &Symbols) -> Result<Vec<Obstacle>, Vec<ParseError>> {
+    let mut obstacles: Vec<Obstacle> = Vec::new();
+    let mut errors: Vec<ParseError> = Vec::new();
+    let mut portals: HashMap<char, Vec<Point>> = HashMap::new();
+    for (index, line) in lines.iter().enumerate() {
+        let tiles = match parse_line(line, index + 1, (lines.len() - 1 - index) as f32, symbols) {
+            Ok(tiles) => tiles,
+            Err(line_errors) => {
+                errors.extend(line_errors);
+                continue;
+            }
+        };
+        for (position, c, kind) in tiles {
+            match kind {
+                Kind::Portal => portals.entry(c).or_default().push(position),
+                kind => obstacles.extend(kind.obstacle(position))
+            }
+        }
+    }
+
+    let mut portal_symbols: Vec<char> = portals.keys().copied().collect();
+    portal_symbols.sort_unstable();
+    for c in portal_symbols {
+        match portals[&c].as_slice() {
+            &[first, second] => {
+                obstacles.push(Obstacle::Portal(Portal(first, second)));
+                obstacles.push(Obstacle::Portal(Portal(second, first)));
+            },
+            ends => errors.extend(ends.iter().map(|Point(x, y)| ParseError {
+                line: lines.len() - *y as usize,
+                column: *x as usize + 1,
+                message: format!("Portal {c} should appear exactly twice, but appears {} times", ends.len())
+            }))
+        }
+    }
+    if errors.is_empty() { Ok(obstacles) } else { Err(errors) }
+}
+
+// Non-empty tiles of the line, with their position and character. Line numbers start at 1 from the
+// top, while y starts at 0 from the bottom
+pub fn parse_line(line: &str, line_number: usize, y: f32, symbols: &Symbols) -> Result<Vec<(Point, char, Kind)>, Vec<ParseError>> {
+    let mut tiles: Vec<(Point, char, Kind)> = Vec::new();
+    let mut errors: Vec<ParseError> = Vec::new();
+    for (index, c) in line.chars().enumerate() {
+        match symbols.kind(c) {
+            None => errors.push(ParseError { line: line_number, column: index + 1, message: format!("Unexpected character {c}") }),
+            Some(Kind::Empty) => continue,
+            Some(kind) => tiles.push((Point(index as f32, y), c, kind))
+        }
+    }
+    if errors.is_empty() { Ok(tiles) } else { Err(errors) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_can_be_given_other_characters() {
+        let symbols: Symbols = "prism=*,portal=ab".parse().unwrap();
+        assert_eq!(symbols.kind('*'), Some(Kind::Prism));
+        assert_eq!(symbols.kind('+'), None);
+        assert_eq!(symbols.kind('b'), Some(Kind::Portal));
+        assert_eq!(symbols.kind('0'), None);
+        assert_eq!(symbols.kind('/'), Some(Kind::RisingMirror));
+    }
+
+    #[test]
+    fn characters_of_two_kinds_are_rejected() {
+        assert_eq!(
+            "prism=^".parse::<Symbols>().err(),
+            Some(String::from("Character ^ is used by both gate-up and prism")));
+        assert_eq!(
+            "absorber=x,prism=x".parse::<Symbols>().err(),
+            Some(String::from("Character x is used by both absorber and prism")));
+        // Moving the gate away first frees its character
+        assert_eq!("prism=^,gate-up=u".parse::<Symbols>().map(|symbols| symbols.kind('^')), Ok(Some(Kind::Prism)));
+    }
+}
@@ -0,0 +1,197 @@
+use std::io::{self, IsTerminal, Read, Write};
+use std::process::{self, Command};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+const CONTROLS: &str = "space: pause, b: step back, n: step forward, +/-: faster/slower, q: quit";
+const FASTEST_TICK: Duration = Duration::from_millis(10);
+const SLOWEST_TICK: Duration = Duration::from_secs(5);
+
+// Puts the terminal back the way it was when playback ends, also when it panics. Ctrl-C exits
+// from a handler that does the same
+struct TerminalGuard {
+    reads_keys: bool
+}
+
+// Character shown at a position, with the colour escape sequences it is drawn with
+#[derive(Clone, PartialEq, Eq)]
+struct Cell {
+    style: String,
+    character: char
+}
+
+// Shows the screens one after the other, and waits on the last one for the keys. When stdin is
+// not a terminal, the screens are only played through once. Screens are rendered when they are
+// shown, so that they do not all have to be kept
+pub fn play(frame_count: usize, mut screen: impl FnMut(usize) -> String, tick: Duration) {
+    let reads_keys = io::stdin().is_terminal();
+    let _guard = TerminalGuard::new(reads_keys);
+    let keys: Option<Receiver<u8>> = if reads_keys { Some(read_keys()) } else { None };
+    let mut tick = tick;
+    let mut index: usize = 0;
+    let mut paused = false;
+    loop {
+        println!("\x1b[H\x1b[2J{}Frame {}/{}{}", screen(index), index + 1, frame_count, if paused { " (paused)" } else { "" });
+        if keys.is_some() {
+            println!("{CONTROLS}");
+        }
+        io::stdout().flush().expect("should be able to write to the terminal");
+        let key: Option<u8> = match &keys {
+            None => {
+                thread::sleep(tick);
+                None
+            },
+            Some(keys) if paused => Some(keys.recv().unwrap_or(b'q')),
+            Some(keys) => match keys.recv_timeout(tick) {
+                Ok(key) => Some(key),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => Some(b'q')
+            }
+        };
+        match key {
+            None if index + 1 < frame_count => index += 1,
+            None if keys.is_some() => paused = true,
+            None | Some(b'q') => break,
+            Some(b' ') => paused = !paused,
+            Some(b'b') => {
+                paused = true;
+                index = index.saturating_sub(1);
+            },
+            Some(b'n') => {
+                paused = true;
+                index = (index + 1).min(frame_count - 1);
+            },
+            Some(b'+') => tick = (tick / 2).max(FASTEST_TICK),
+            Some(b'-') => tick = (tick * 2).min(SLOWEST_TICK),
+            Some(_) => {}
+        }
+    }
+}
+
+// Recording of the screens in the asciicast v2 format, with one screen every tick. The first
+// screen is drawn whole, and each of the others only writes the cells that changed
+pub fn to_asciicast(frame_count: usize, mut screen: impl FnMut(usize) -> String, tick: Duration) -> String {
+    let mut events = String::new();
+    let (mut width, mut height) = (0, 0);
+    let mut previous: Vec<Vec<Cell>> = Vec::new();
+    for index in 0..frame_count {
+        let text = format!("{}Frame {}/{}\n", screen(index), index + 1, frame_count);
+        let cells: Vec<Vec<Cell>> = text.lines().map(cells).collect();
+        width = cells.iter().map(Vec::len).fold(width, usize::max);
+        height = height.max(cells.len());
+        let output = if index == 0 { format!("\x1b[H\x1b[2J{}", draw_changes(&[], &cells)) } else { draw_changes(&previous, &cells) };
+        events.push_str(&format!("[{:.3}, \"o\", {}]\n", index as f64 * tick.as_secs_f64(), json_string(&output)));
+        previous = cells;
+    }
+    format!("{{\"version\": 2, \"width\": {width}, \"height\": {height}}}\n{events}")
+}
+
+impl TerminalGuard {
+    fn new(reads_keys: bool) -> TerminalGuard {
+        print!("\x1b[?25l");
+        let _ = ctrlc::set_handler(move || {
+            restore_terminal(reads_keys);
+            process::exit(130);
+        });
+        TerminalGuard { reads_keys }
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) { restore_terminal(self.reads_keys); }
+}
+
+fn restore_terminal(reads_keys: bool) {
+    print!("\x1b[?25h");
+    let _ = io::stdout().flush();
+    if reads_keys {
+        set_terminal_echo(true);
+    }
+}
+
+fn cells(line: &str) -> Vec<Cell> {
+    let mut cells: Vec<Cell> = Vec::new();
+    let mut style = String::new();
+    let mut escape: Option<String> = None;
+    for c in line.chars() {
+        match (&mut escape, c) {
+            (None, '\x1b') => escape = Some(String::from(c)),
+            (Some(sequence), 'm') => {
+                sequence.push(c);
+                // Resetting the colours ends the style instead of adding to it
+                if sequence == "\x1b[0m" { style.clear() } else { style.push_str(sequence) }
+                escape = None;
+            },
+            (Some(sequence), _) => sequence.push(c),
+            (None, _) => cells.push(Cell { style: style.clone(), character: c })
+        }
+    }
+    cells
+}
+
+// Escape sequences turning the previous screen into the next one, moving the cursor to each cell
+// that changed unless it is already there. Lines that got shorter are cleared past their end
+fn draw_changes(previous: &[Vec<Cell>], next: &[Vec<Cell>]) -> String {
+    let mut output = String::new();
+    let mut cursor: Option<(usize, usize)> = None;
+    for row in 0..previous.len().max(next.len()) {
+        let (before, after): (&[Cell], &[Cell]) =
+            (previous.get(row).map_or(&[], Vec::as_slice), next.get(row).map_or(&[], Vec::as_slice));
+        for (column, cell) in after.iter().enumerate() {
+            if before.get(column) != Some(cell) {
+                if cursor != Some((row, column)) {
+                    output.push_str(&format!("\x1b[{};{}H", row + 1, column + 1));
+                }
+                cursor = Some((row, column + 1));
+                if cell.style.is_empty() {
+                    output.push(cell.character);
+                } else {
+                    output.push_str(&format!("{}{}\x1b[0m", cell.style, cell.character));
+                }
+            }
+        }
+        if after.len() < before.len() {
+            output.push_str(&format!("\x1b[{};{}H\x1b[K", row + 1, after.len() + 1));
+            cursor = None;
+        }
+    }
+    output
+}
+
+// Sends every key pressed. Without stty, the keys only arrive once enter is pressed
+fn read_keys() -> Receiver<u8> {
+    set_terminal_echo(false);
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for key in io::stdin().lock().bytes() {
+            let Ok(key) = key else { return };
+            if sender.send(key).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+// Switches the terminal between its usual line editing and reading keys as they are pressed
+fn set_terminal_echo(enabled: bool) {
+    let arguments = if enabled { ["icanon", "echo"] } else { ["-icanon", "-echo"] };
+    let _ = Command::new("stty").args(arguments).status();
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c)
+        }
+    }
+    json.push('"');
+    json
+}
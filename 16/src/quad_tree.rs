@@ -1,6 +0,0 @@
-mod node;
-mod quad_tree;
-mod positioned_2d;
-
-pub use self::quad_tree::QuadTree;
-pub use self::positioned_2d::Positioned2D;
\ No newline at end of file
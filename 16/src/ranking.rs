@@ -0,0 +1,49 @@
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use spatial::geometry::Ray;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RankedRay {
+    pub ray: Ray,
+    pub energized: usize
+}
+
+// Evaluates the rays on the given number of threads, which take the next ray to evaluate from a
+// shared counter. The ranking goes from the most energized tiles to the fewest, and ties keep the
+// order of the given rays, so it is the same whatever the number of threads
+pub fn rank_rays(rays: &[Ray], threads: usize, energized_count: impl Fn(&Ray) -> usize + Sync) -> Vec<RankedRay> {
+    let next_ray = AtomicUsize::new(0);
+    let mut counts: Vec<(usize, usize)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| scope.spawn(|| {
+                let mut counts: Vec<(usize, usize)> = Vec::new();
+                loop {
+                    let index = next_ray.fetch_add(1, Ordering::Relaxed);
+                    match rays.get(index) {
+                        None => return counts,
+                        Some(ray) => counts.push((index, energized_count(ray)))
+                    }
+                }
+            }))
+            .collect();
+        workers.into_iter()
+            .flat_map(|worker| worker.join().expect("Ray evaluation thread should not panic"))
+            .collect()
+    });
+    counts.sort_by_key(|&(index, energized)| (Reverse(energized), index));
+    counts.into_iter()
+        .map(|(index, energized)| RankedRay { ray: rays[index], energized })
+        .collect()
+}
+
+pub fn to_csv(ranking: &[RankedRay]) -> String {
+    let mut csv = String::from("rank,x,y,dx,dy,energized\n");
+    for (rank, RankedRay { ray, energized }) in ranking.iter().enumerate() {
+        csv.push_str(&format!("{},{},{},{},{},{energized}\n", rank + 1, ray.0.0, ray.0.1, ray.1.0, ray.1.1));
+    }
+    csv
+}
+
+pub fn default_threads() -> usize { thread::available_parallelism().map_or(1, |threads| threads.get()) }
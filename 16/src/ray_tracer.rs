@@ -1,73 +1,123 @@
 use std::collections::{HashSet, HashMap};
 
-use crate::quad_tree::{QuadTree, Positioned2D};
-use crate::geometry::{Ray, Point, Vector, Bounds};
+use spatial::quad_tree::{Positioned2D, SpatialIndex};
+use spatial::geometry::{Ray, Point, Vector, Bounds, GridDimensions};
 use crate::obstacle::Obstacle;
+use crate::optics::OpticalElement;
+use crate::ranking::{rank_rays, RankedRay};
 
-pub fn find_most_energized_configuration(qt: &QuadTree<Obstacle>, top_right_tile: &Point) -> HashMap<Point, Vec<Vector>> {
+// How beams lose energy on the way. Beams start with an energy of 1, elements sending out several
+// beams divide it between them, and mirrors keep the given fraction of it
+pub struct Attenuation {
+    pub mirror_factor: f32,
+    // Beams with less energy than this are dropped
+    pub min_energy: f32,
+    pub max_segments: usize
+}
+
+// Total energy of the beams going through each tile
+pub struct Intensities {
+    pub tiles: HashMap<Point, f32>,
+    // Whether the tracing stopped at the segment limit rather than when every beam was gone
+    pub truncated: bool
+}
+
+pub fn rank_edge_rays(qt: &(impl SpatialIndex<Obstacle> + Sync), dimensions: &GridDimensions, threads: usize) -> Vec<RankedRay> {
+    rank_rays(&edge_rays(dimensions), threads, |ray| find_energized_tiles(ray, qt, dimensions).len())
+}
+
+// Rays entering the grid from every tile on its edges
+pub fn edge_rays(dimensions: &GridDimensions) -> Vec<Ray> {
+    let (width, height) = (dimensions.width as f32, dimensions.height as f32);
     let mut rays: Vec<Ray> = Vec::new();
-    for x in (0..=top_right_tile.0 as i32).map(|x| x as f32) {
-        rays.push(Ray(Point(x, top_right_tile.1 + 1.0), Vector::DOWN));
+    for x in (0..dimensions.width).map(|x| x as f32) {
+        rays.push(Ray(Point(x, height), Vector::DOWN));
         rays.push(Ray(Point(x, -1.0), Vector::UP));
     }
-    for y in (0..=top_right_tile.1 as i32).map(|y| y as f32) {
+    for y in (0..dimensions.height).map(|y| y as f32) {
         rays.push(Ray(Point(-1.0, y), Vector::RIGHT));
-        rays.push(Ray(Point(top_right_tile.0 + 1.0, y), Vector::LEFT));
+        rays.push(Ray(Point(width, y), Vector::LEFT));
     }
-    rays.iter()
-        .map(|ray| (ray, find_energized_tiles(ray, qt, top_right_tile)))
-        .max_by(|(_, tiles1), (_, tiles2)| tiles1.len().cmp(&tiles2.len()))
-        .map(|(ray, tiles)| {
-            println!("Best Ray is {:?}", ray);
-            tiles
-        })
-        .unwrap_or(HashMap::new())
+    rays
 }
 
-pub fn find_energized_tiles(ray: &Ray, qt: &QuadTree<Obstacle>, top_right_tile: &Point) -> HashMap<Point, Vec<Vector>> {
+pub fn find_energized_tiles(ray: &Ray, qt: &impl SpatialIndex<Obstacle>, dimensions: &GridDimensions) -> HashMap<Point, Vec<Vector>> {
     // We check the intersection with an expanded boundary because we want to count
     // rays that run along the edges
     let expanded_bounds =
-        Bounds::from_two(&Point(-1.0, -1.0), &(*top_right_tile + Vector(1.0, 1.0)));
-    let tiles: HashMap<Point, Vec<Vector>> = find_energized_tiles_rec(qt, &ray, &mut HashSet::new(), &expanded_bounds);
-    tiles.iter()
-        .filter(|(pos, _)| Bounds::from_origin(top_right_tile).contains(pos))
-        .map(|(pos, vectors)| (pos.clone(), vectors.clone()))
+        Bounds::from_two(&Point(-1.0, -1.0), &(dimensions.top_right_tile() + Vector(1.0, 1.0)));
+    let mut tiles: HashMap<Point, Vec<Vector>> = HashMap::new();
+    let mut visited: HashSet<Ray> = HashSet::new();
+    let mut worklist: Vec<Ray> = vec![*ray];
+    while let Some(ray) = worklist.pop() {
+        if !visited.insert(ray) {
+            continue;
+        }
+        let end: Point = match qt.intersect(&ray, true) {
+            None => match ray.exit_bounds(&expanded_bounds) {
+                None => continue,
+                Some(bounds_hit) => bounds_hit
+            },
+            Some(hit) => {
+                worklist.extend(hit.redirect(&ray));
+                *hit.position()
+            }
+        };
+        for (point, vectors) in get_discrete_points_until(&ray.0, &end) {
+            tiles.entry(point).or_default().extend(vectors);
+        }
+    }
+    tiles.into_iter()
+        .filter(|(pos, _)| dimensions.bounds().contains(pos))
         .collect()
 }
 
-fn find_energized_tiles_rec(
-    qt: &QuadTree<Obstacle>,
+// Beams can go round a loop of mirrors and portals forever without being divided, so the number
+// of segments is capped
+pub fn find_tile_intensities(
     ray: &Ray,
-    visited: &mut HashSet<Ray>,
-    expanded_bounds: &Bounds
-) -> HashMap<Point, Vec<Vector>> {
-    if visited.contains(ray) {
-        return HashMap::new();
-    }
-    visited.insert(ray.clone());
-    match qt.intersect(ray, true) {
-        None => {
-            let bounds_hit = match ray.intersect_bounds(&expanded_bounds) {
-                None => return HashMap::new(),
-                Some(hit) => hit
-            };
-            return get_discrete_points_until(&ray.0, &bounds_hit);
+    qt: &impl SpatialIndex<Obstacle>,
+    dimensions: &GridDimensions,
+    attenuation: &Attenuation
+) -> Intensities {
+    let expanded_bounds =
+        Bounds::from_two(&Point(-1.0, -1.0), &(dimensions.top_right_tile() + Vector(1.0, 1.0)));
+    let mut tiles: HashMap<Point, f32> = HashMap::new();
+    let mut segments: usize = 0;
+    let mut truncated = false;
+    // Beams with their energy, and whether their first tile is still to be counted. Beams leaving
+    // an obstacle start on its tile, which the beam reaching it has already lit
+    let mut worklist: Vec<(Ray, f32, bool)> = vec![(*ray, 1.0, true)];
+    while let Some((ray, energy, count_origin)) = worklist.pop() {
+        if segments == attenuation.max_segments {
+            truncated = true;
+            break;
         }
-        Some(hit) => {
-            let mut energized_by_ray: HashMap<Point, Vec<Vector>> = get_discrete_points_until(&ray.0, hit.position());
-            let new_rays: Vec<Ray> = hit.reflect(ray);
-            let energized_by_new_rays =
-                new_rays.iter().flat_map(|new_ray| find_energized_tiles_rec(qt, new_ray, visited, expanded_bounds));
-            for (point, vectors) in energized_by_new_rays {
-                if !energized_by_ray.contains_key(&point) {
-                    energized_by_ray.insert(point, Vec::new());
+        segments += 1;
+        let end: Point = match qt.intersect(&ray, true) {
+            None => match ray.exit_bounds(&expanded_bounds) {
+                None => continue,
+                Some(bounds_hit) => bounds_hit
+            },
+            Some(hit) => {
+                let outputs: Vec<Ray> = hit.redirect(&ray);
+                let factor = if let Obstacle::Mirror(_) = hit { attenuation.mirror_factor } else { 1.0 };
+                let output_energy = energy * factor / outputs.len().max(1) as f32;
+                if output_energy >= attenuation.min_energy {
+                    worklist.extend(outputs.into_iter().map(|output| (output, output_energy, output.0 != *hit.position())));
                 }
-                energized_by_ray.get_mut(&point).unwrap().extend(vectors);
+                *hit.position()
+            }
+        };
+        let origin = discretize(ray.0);
+        for point in get_discrete_points_until(&ray.0, &end).into_keys() {
+            if count_origin || point != origin {
+                *tiles.entry(point).or_default() += energy;
             }
-            return energized_by_ray;
         }
-    };
+    }
+    tiles.retain(|pos, _| dimensions.bounds().contains(pos));
+    Intensities { tiles, truncated }
 }
 
 fn get_discrete_points_until(src: &Point, dst: &Point) -> HashMap<Point, Vec<Vector>> {
@@ -0,0 +1,155 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::grid_tracer::{Beam, GridIndex, Segment};
+
+// Graph whose nodes are the beams leaving obstacles, with an edge from each beam to the beams
+// leaving the obstacle it hits. Beams that feed each other form strongly connected components,
+// which energize the same tiles. Once those are condensed the graph is acyclic, so the tiles
+// reachable from every component are computed once, and each entry beam only has to follow its
+// first segment
+pub struct SegmentGraph<'a> {
+    grid: &'a GridIndex,
+    component_of: HashMap<Beam, usize>,
+    // Tiles energized from each component, including its own
+    reachable: Vec<TileSet>
+}
+
+// Dense bitset over the tiles of the grid, row by row from the bottom left corner
+#[derive(Clone)]
+struct TileSet {
+    width: i32,
+    words: Vec<u64>
+}
+
+impl<'a> SegmentGraph<'a> {
+    pub fn new(grid: &'a GridIndex) -> SegmentGraph<'a> {
+        let beams: Vec<Beam> = grid.obstacle_beams().collect();
+        let node_of: HashMap<Beam, usize> = beams.iter().enumerate().map(|(node, beam)| (*beam, node)).collect();
+        let segments: Vec<Segment> = beams.iter().map(|beam| grid.segment(beam)).collect();
+        let successors: Vec<Vec<usize>> =
+            segments.iter()
+                .map(|segment| segment.next.iter().map(|beam| node_of[beam]).collect())
+                .collect();
+
+        let components: Vec<Vec<usize>> = strongly_connected_components(&successors);
+        let mut component_of_node: Vec<usize> = vec![0; beams.len()];
+        for (component, nodes) in components.iter().enumerate() {
+            for &node in nodes {
+                component_of_node[node] = component;
+            }
+        }
+        // Components come out with every successor before its predecessors
+        let mut reachable: Vec<TileSet> = Vec::with_capacity(components.len());
+        for (component, nodes) in components.iter().enumerate() {
+            let mut tiles = TileSet::new(grid.width(), grid.height());
+            for &node in nodes {
+                segments[node].tiles.iter().for_each(|&tile| tiles.insert(tile));
+                for &next in successors[node].iter() {
+                    if component_of_node[next] != component {
+                        tiles.union_with(&reachable[component_of_node[next]]);
+                    }
+                }
+            }
+            reachable.push(tiles);
+        }
+        let component_of: HashMap<Beam, usize> =
+            beams.into_iter().zip(component_of_node).collect();
+        SegmentGraph { grid, component_of, reachable }
+    }
+
+    pub fn energized_count(&self, start: Beam) -> usize { self.energized_set(start).len() }
+
+    pub fn energized_tiles(&self, start: Beam) -> HashSet<(i32, i32)> { self.energized_set(start).tiles().collect() }
+
+    pub fn component_count(&self) -> usize { self.reachable.len() }
+
+    fn energized_set(&self, start: Beam) -> TileSet {
+        let segment = self.grid.segment(&start);
+        let mut tiles = TileSet::new(self.grid.width(), self.grid.height());
+        segment.tiles.iter().for_each(|&tile| tiles.insert(tile));
+        for beam in segment.next.iter() {
+            tiles.union_with(&self.reachable[self.component_of[beam]]);
+        }
+        tiles
+    }
+}
+
+impl TileSet {
+    fn new(width: i32, height: i32) -> TileSet {
+        TileSet { width, words: vec![0; ((width * height) as usize).div_ceil(64)] }
+    }
+
+    fn insert(&mut self, (x, y): (i32, i32)) {
+        let tile = (y * self.width + x) as usize;
+        self.words[tile / 64] |= 1 << (tile % 64);
+    }
+
+    fn union_with(&mut self, other: &TileSet) {
+        self.words.iter_mut().zip(other.words.iter()).for_each(|(word, other)| *word |= other);
+    }
+
+    fn len(&self) -> usize { self.words.iter().map(|word| word.count_ones() as usize).sum() }
+
+    fn tiles(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        (0..self.words.len() * 64)
+            .filter(|&tile| self.words[tile / 64] >> (tile % 64) & 1 == 1)
+            .map(|tile| ((tile as i32) % self.width, (tile as i32) / self.width))
+    }
+}
+
+// Tarjan's algorithm with an explicit call stack, as the chains of beams are too long to recurse
+// on. The components come out in reverse topological order
+fn strongly_connected_components(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    const UNVISITED: usize = usize::MAX;
+    let mut index: Vec<usize> = vec![UNVISITED; successors.len()];
+    let mut low_link: Vec<usize> = vec![0; successors.len()];
+    let mut on_stack: Vec<bool> = vec![false; successors.len()];
+    let mut stack: Vec<usize> = Vec::new();
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    let mut next_index: usize = 0;
+
+    for root in 0..successors.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        // Nodes being visited, with the position of the next successor to look at
+        let mut call_stack: Vec<(usize, usize)> = vec![(root, 0)];
+        index[root] = next_index;
+        low_link[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        while let Some(&(node, edge)) = call_stack.last() {
+            if let Some(&next) = successors[node].get(edge) {
+                call_stack.last_mut().unwrap().1 += 1;
+                if index[next] == UNVISITED {
+                    index[next] = next_index;
+                    low_link[next] = next_index;
+                    next_index += 1;
+                    stack.push(next);
+                    on_stack[next] = true;
+                    call_stack.push((next, 0));
+                } else if on_stack[next] {
+                    low_link[node] = low_link[node].min(index[next]);
+                }
+                continue;
+            }
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                low_link[parent] = low_link[parent].min(low_link[node]);
+            }
+            if low_link[node] == index[node] {
+                let mut component: Vec<usize> = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+    components
+}
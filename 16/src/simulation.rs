@@ -0,0 +1,89 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::grid_tracer::{Beam, Direction, GridIndex, Segment};
+use crate::parser::{Kind, Symbols};
+
+// Beams after a number of ticks, and the tiles lit for the first time during that tick
+pub struct Frame {
+    pub beams: Vec<Beam>,
+    pub lit: Vec<(i32, i32)>
+}
+
+// Tiles lit up to a frame, built up from the frames before it
+pub struct Replay<'a> {
+    frames: &'a [Frame],
+    next: usize,
+    energized: HashSet<(i32, i32)>
+}
+
+// Every tick moves each beam on by one tile. Beams that have already left the same tile in the
+// same direction are dropped, like in the tracers, so the simulation ends even with loops
+pub fn simulate(grid: &GridIndex, start: Beam) -> Vec<Frame> {
+    let mut frames: Vec<Frame> = vec![Frame { beams: vec![start], lit: Vec::new() }];
+    let mut energized: HashSet<(i32, i32)> = HashSet::new();
+    let mut seen: HashSet<Beam> = HashSet::from([start]);
+    loop {
+        let previous = frames.last().expect("should start with a frame");
+        let mut lit: Vec<(i32, i32)> = Vec::new();
+        let mut beams: Vec<Beam> = Vec::new();
+        for beam in previous.beams.iter() {
+            let Segment { tiles, next } = grid.step(beam);
+            // Beams coming out of a portal light the paired tile
+            let reached = tiles.into_iter().chain(next.iter().map(|beam| (beam.x, beam.y)));
+            lit.extend(reached.filter(|&tile| energized.insert(tile)));
+            beams.extend(next.into_iter().filter(|&beam| seen.insert(beam)));
+        }
+        if beams.is_empty() && lit.is_empty() {
+            return frames;
+        }
+        frames.push(Frame { beams, lit });
+    }
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(frames: &'a [Frame]) -> Replay<'a> { Replay { frames, next: 0, energized: HashSet::new() } }
+
+    // Going back a frame starts over from the first one
+    pub fn seek(&mut self, index: usize) -> &HashSet<(i32, i32)> {
+        if index < self.next.saturating_sub(1) {
+            self.next = 0;
+            self.energized.clear();
+        }
+        while self.next <= index {
+            self.energized.extend(self.frames[self.next].lit.iter().copied());
+            self.next += 1;
+        }
+        &self.energized
+    }
+}
+
+// Puzzle grid with an arrow for each beam and '#' for the empty tiles that are lit
+pub fn render(frame: &Frame, energized: &HashSet<(i32, i32)>, lines: &[&str], symbols: &Symbols) -> String {
+    let beams: HashMap<(i32, i32), Direction> =
+        frame.beams.iter()
+            .map(|beam| ((beam.x, beam.y), beam.direction))
+            .collect();
+    let mut text = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        let y = (lines.len() - 1 - index) as i32;
+        for (x, c) in line.chars().enumerate() {
+            let x = x as i32;
+            match beams.get(&(x, y)) {
+                Some(direction) => text.push_str(&format!("\x1b[1;33m{}\x1b[0m", arrow(direction))),
+                None if symbols.kind(c) == Some(Kind::Empty) && energized.contains(&(x, y)) => text.push('#'),
+                None => text.push(c)
+            }
+        }
+        text.push('\n');
+    }
+    text
+}
+
+fn arrow(direction: &Direction) -> char {
+    match direction {
+        Direction::Up => '^',
+        Direction::Down => 'v',
+        Direction::Left => '<',
+        Direction::Right => '>'
+    }
+}
@@ -3,6 +3,9 @@ use std::env;
 use std::fs;
 use std::cmp::max;
 use std::cmp::min;
+use std::iter;
+
+use cycle::{Strategy, Usage};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -13,6 +16,8 @@ fn main() {
 
     let input: Vec<&str> = contents.trim().split("\n").collect();
     let (graph, directions) = parse(&input);
+    let strategy: Strategy = parse_flag(&args, "--cycle-detection").unwrap_or(Strategy::HashMap);
+    let show_report = has_flag(&args, "--report");
 
     match version {
         1 => {
@@ -20,14 +25,25 @@ fn main() {
             println!("Result is {result}");
         },
         2 => {
-            let result = graph.count_moves_parallel(&directions);
-            println!("Result is {result}");
+            match graph.count_moves_parallel(&directions, strategy, show_report) {
+                Ok(result) => println!("Result is {result}"),
+                Err(error) => eprintln!("No result: {error}")
+            }
         },
         _ => panic!("Unknown version {version}")
     };
 }
 
-fn parse(lines: &Vec<&str>) -> (Graph, Vec<Direction>) {
+fn parse_flag<T: std::str::FromStr>(args: &[String], name: &str) -> Option<T> {
+    args.iter()
+        .position(|arg| arg == name)
+        .map(|index| args.get(index + 1).expect("Flag should have a value"))
+        .map(|value| value.parse().unwrap_or_else(|_| panic!("Invalid value {value} for {name}")))
+}
+
+fn has_flag(args: &[String], name: &str) -> bool { args.iter().any(|arg| arg == name) }
+
+fn parse(lines: &[&str]) -> (Graph, Vec<Direction>) {
     let directions = parse_directions(lines[0]);
     let mut graph_data: HashMap<String, Node> = HashMap::new();
     for line in lines.iter().skip(2) {
@@ -69,7 +85,7 @@ impl Graph {
         }
     }
 
-    fn count_moves(&self, start: &str, target: &str, directions: &Vec<Direction>) -> u64 {
+    fn count_moves(&self, start: &str, target: &str, directions: &[Direction]) -> u64 {
         let mut current = start;
         let mut sum: u64 = 0;
         for direction in directions.iter().cycle() {
@@ -82,48 +98,58 @@ impl Graph {
         sum
     }
 
-    fn count_moves_parallel(&self, directions: &Vec<Direction>) -> u64 {
+    fn count_moves_parallel(&self, directions: &[Direction], strategy: Strategy, show_report: bool) -> Result<u64, String> {
         let start_positions: Vec<&str> =
             self.data.keys()
                 .filter(|k| k.chars().nth(2).unwrap() == 'A')
                 .map(|k| k.as_str())
                 .collect();
 
+        if start_positions.is_empty() {
+            return Err(String::from("there is no node ending with A to start from"));
+        }
+
         let loop_sizes: Vec<u64> =
             start_positions.iter()
                 .map(|start_pos| {
-                    let loops: Vec<Loop> = self.find_loops(start_pos, directions);
-                    loops.iter().map(|l| l.size).max().unwrap()
+                    let (loops, usage) = self.find_loops(start_pos, directions, strategy);
+                    let Some(size) = loops.iter().map(|l| l.size).max() else {
+                        return Err(format!("{start_pos} never reaches a node ending with Z"));
+                    };
+                    if show_report {
+                        let steps: Vec<u64> = loops.iter().map(|l| l.total_steps).collect();
+                        println!("{start_pos}: reaches a Z node after {steps:?} steps, looping every {size}; cycle detection took {usage}");
+                    }
+                    Ok(size)
                 })
-                .collect();
-        
+                .collect::<Result<_, _>>()?;
+
         let gcd_all = loop_sizes.clone().into_iter().reduce(gcd).unwrap();
-        loop_sizes[1..].iter().fold(loop_sizes[0], |acc, elem| acc * (elem / gcd_all))
+        Ok(loop_sizes[1..].iter().fold(loop_sizes[0], |acc, elem| acc * (elem / gcd_all)))
     }
 
-    fn find_loops(&self, start_position: &str, directions: &Vec<Direction>) -> Vec<Loop> {
-        let mut tracing: HashMap<(&str, usize), u64> = HashMap::new();
-        let mut current_node: &str = start_position;
-        let mut current_step: u64 = 0;
-
-        for current_direction_idx in (0..directions.len()).cycle() {
-            let tracing_element = (current_node, current_direction_idx);
-            if tracing.contains_key(&tracing_element) { // We've see this before - we found a loop!
-                break;
-            }
-            tracing.insert(tracing_element, current_step);
-            current_node = self.go(current_node, directions[current_direction_idx]);
-            current_step += 1;
-        }
-
-        let loop_start = current_node;
-        let steps_until_loop_start: u64 = self.count_moves(start_position, loop_start, directions);
-        let loop_size: u64 = current_step - steps_until_loop_start;
-
-        tracing.into_iter()
-            .filter(|((node, _), _)| node.chars().nth(2).unwrap() == 'Z')
-            .map(|(_, steps)| Loop::from(steps, loop_size))
-            .collect()
+    // The state is the current node along with the position in the directions, and it repeats
+    // after a while. Every Z node up to the end of the first loop is reached again once per loop
+    fn find_loops<'a>(
+        &'a self,
+        start_position: &'a str,
+        directions: &[Direction],
+        strategy: Strategy
+    ) -> (Vec<Loop>, Usage) {
+        let step = |&(node, direction_idx): &(&'a str, usize)| {
+            (self.go(node, directions[direction_idx]), (direction_idx + 1) % directions.len())
+        };
+        let start = (start_position, 0);
+        let (found_loop, usage) = cycle::find_cycle(&start, step, strategy);
+
+        let loops: Vec<Loop> =
+            iter::successors(Some(start), |state| Some(step(state)))
+                .take((found_loop.offset + found_loop.period) as usize)
+                .enumerate()
+                .filter(|(_, (node, _))| node.chars().nth(2).unwrap() == 'Z')
+                .map(|(steps, _)| Loop::from(steps as u64, found_loop.period))
+                .collect();
+        (loops, usage)
     }
 }
 
@@ -0,0 +1,200 @@
+use std::fmt;
+
+use crate::rational::Rational;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExtrapolationError {
+    EmptySequence,
+    // The rows ran out before the differences became all zeroes, so the sequence has no
+    // polynomial of a lower degree than its length that generates it
+    NoZeroRow,
+    Overflow
+}
+
+impl fmt::Display for ExtrapolationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExtrapolationError::EmptySequence => write!(f, "the sequence is empty"),
+            ExtrapolationError::NoZeroRow => write!(f, "the differences never reach an all-zero row"),
+            ExtrapolationError::Overflow => write!(f, "the arithmetic overflowed")
+        }
+    }
+}
+
+// Table of successive differences, where rows[0] is the sequence itself and the last row
+// is the first one made only of zeroes
+pub struct DifferenceTable {
+    rows: Vec<Vec<i128>>
+}
+
+impl DifferenceTable {
+    pub fn from_sequence(sequence: &[i64]) -> Result<DifferenceTable, ExtrapolationError> {
+        if sequence.is_empty() {
+            return Err(ExtrapolationError::EmptySequence);
+        }
+        let mut rows: Vec<Vec<i128>> = vec![sequence.iter().map(|x| *x as i128).collect()];
+        loop {
+            let last_row: &Vec<i128> = rows.last().unwrap();
+            if last_row.iter().all(|x| *x == 0) {
+                break;
+            }
+            if last_row.len() == 1 {
+                return Err(ExtrapolationError::NoZeroRow);
+            }
+            let diff_row: Vec<i128> =
+                last_row.windows(2)
+                    .map(|pair| pair[1].checked_sub(pair[0]).ok_or(ExtrapolationError::Overflow))
+                    .collect::<Result<_, _>>()?;
+            rows.push(diff_row);
+        }
+        Ok(DifferenceTable { rows })
+    }
+
+    // Degree of the generating polynomial. The all-zero sequence is reported as degree 0
+    pub fn degree(&self) -> usize { self.rows.len().saturating_sub(2) }
+
+    // Newton's backward formula from the last element: p(last + k) = sum over j of
+    // row_j[last] * (k + j - 1 choose j)
+    pub fn extrapolate_forwards(&self, steps: u64) -> Result<i128, ExtrapolationError> {
+        let diagonal: Vec<i128> = self.rows.iter().map(|row| *row.last().unwrap()).collect();
+        extrapolate(&diagonal, steps, false)
+    }
+
+    // Newton's forward formula from the first element, at -k: p(-k) = sum over j of
+    // row_j[0] * (-1)^j * (k + j - 1 choose j)
+    pub fn extrapolate_backwards(&self, steps: u64) -> Result<i128, ExtrapolationError> {
+        let diagonal: Vec<i128> = self.rows.iter().map(|row| row[0]).collect();
+        extrapolate(&diagonal, steps, true)
+    }
+
+    // Coefficients of the generating polynomial p, lowest degree first, such that p(n) is the
+    // n-th element of the sequence (starting at n = 0)
+    pub fn coefficients(&self) -> Result<Vec<Rational>, ExtrapolationError> {
+        // Newton's forward formula: p(n) = sum over j of row_j[0] * n(n-1)...(n-j+1) / j!
+        let mut coefficients: Vec<Rational> = vec![Rational::ZERO; self.degree() + 1];
+        let mut falling_factorial: Vec<i128> = vec![1];
+        let mut factorial: i128 = 1;
+        for (j, row) in self.rows.iter().enumerate().take(self.degree() + 1) {
+            if j > 0 {
+                factorial = factorial.checked_mul(j as i128).ok_or(ExtrapolationError::Overflow)?;
+                falling_factorial = multiply_by_linear(&falling_factorial, j as i128 - 1)?;
+            }
+            let scale = Rational::new(row[0], factorial).ok_or(ExtrapolationError::Overflow)?;
+            for (power, coefficient) in falling_factorial.iter().enumerate() {
+                let term = scale.checked_mul(&Rational::from_integer(*coefficient)).ok_or(ExtrapolationError::Overflow)?;
+                coefficients[power] = coefficients[power].checked_add(&term).ok_or(ExtrapolationError::Overflow)?;
+            }
+        }
+        Ok(coefficients)
+    }
+}
+
+// Sum of the differences weighted by (steps + j - 1 choose j), so that the cost only depends on
+// the degree and not on how far the sequence goes
+fn extrapolate(diagonal: &[i128], steps: u64, alternate: bool) -> Result<i128, ExtrapolationError> {
+    // The zero differences at the end add nothing, but their binomials could still overflow
+    let nonzero: usize = diagonal.len() - diagonal.iter().rev().take_while(|x| **x == 0).count();
+    let mut value: i128 = 0;
+    let mut binomial: i128 = 1;
+    for (j, difference) in diagonal.iter().enumerate().take(nonzero) {
+        if j > 0 {
+            // (k + j - 1 choose j) = (k + j - 2 choose j - 1) * (k + j - 1) / j, which divides exactly
+            let factor = (steps as i128).checked_add(j as i128 - 1).ok_or(ExtrapolationError::Overflow)?;
+            binomial = binomial.checked_mul(factor).ok_or(ExtrapolationError::Overflow)? / j as i128;
+        }
+        let term = difference.checked_mul(binomial).ok_or(ExtrapolationError::Overflow)?;
+        value =
+            if alternate && j % 2 == 1 { value.checked_sub(term) } else { value.checked_add(term) }
+                .ok_or(ExtrapolationError::Overflow)?;
+    }
+    Ok(value)
+}
+
+// Multiplies the polynomial by (n - root)
+fn multiply_by_linear(polynomial: &[i128], root: i128) -> Result<Vec<i128>, ExtrapolationError> {
+    let mut result: Vec<i128> = vec![0; polynomial.len() + 1];
+    for (power, coefficient) in polynomial.iter().enumerate() {
+        let shifted = result[power + 1].checked_add(*coefficient);
+        let scaled = coefficient.checked_mul(root).and_then(|x| result[power].checked_sub(x));
+        result[power + 1] = shifted.ok_or(ExtrapolationError::Overflow)?;
+        result[power] = scaled.ok_or(ExtrapolationError::Overflow)?;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Extrapolation one step at a time, as the puzzle describes it
+    fn extrapolate_by_steps(sequence: &[i64], steps: u64) -> i128 {
+        let mut sequence: Vec<i128> = sequence.iter().map(|x| *x as i128).collect();
+        for _ in 0..steps {
+            let mut row: Vec<i128> = sequence.clone();
+            let mut next: i128 = 0;
+            while !row.iter().all(|x| *x == 0) {
+                next += row.last().unwrap();
+                row = row.windows(2).map(|pair| pair[1] - pair[0]).collect();
+            }
+            sequence.push(next);
+        }
+        *sequence.last().unwrap()
+    }
+
+    #[test]
+    fn tables_end_with_the_first_all_zero_row() {
+        let table = DifferenceTable::from_sequence(&[1, 3, 6, 10, 15, 21]).unwrap();
+        assert_eq!(table.rows, vec![vec![1, 3, 6, 10, 15, 21], vec![2, 3, 4, 5, 6], vec![1, 1, 1, 1], vec![0, 0, 0]]);
+        assert_eq!(table.degree(), 2);
+        assert_eq!(DifferenceTable::from_sequence(&[0, 0]).unwrap().degree(), 0);
+    }
+
+    #[test]
+    fn extrapolation_matches_the_puzzle() {
+        let sequences: [&[i64]; 3] = [&[0, 3, 6, 9, 12, 15], &[1, 3, 6, 10, 15, 21], &[10, 13, 16, 21, 30, 45]];
+        let tables: Vec<DifferenceTable> = sequences.iter().map(|sequence| DifferenceTable::from_sequence(sequence).unwrap()).collect();
+        let forwards: Vec<i128> = tables.iter().map(|table| table.extrapolate_forwards(1).unwrap()).collect();
+        let backwards: Vec<i128> = tables.iter().map(|table| table.extrapolate_backwards(1).unwrap()).collect();
+        assert_eq!(forwards, vec![18, 28, 68]);
+        assert_eq!(backwards, vec![-3, 0, 5]);
+        for (sequence, table) in sequences.iter().zip(tables.iter()) {
+            for steps in 0..10 {
+                assert_eq!(table.extrapolate_forwards(steps).unwrap(), extrapolate_by_steps(sequence, steps));
+            }
+        }
+    }
+
+    #[test]
+    fn far_steps_are_evaluated_directly() {
+        let table = DifferenceTable::from_sequence(&[10, 13, 16, 21, 30, 45]).unwrap();
+        // p(n) = (n^3 - 3n^2 + 11n + 30) / 3 at n = 5 + 10^12, and the same on the other side
+        let n: i128 = 5 + 1_000_000_000_000;
+        assert_eq!(table.extrapolate_forwards(1_000_000_000_000), Ok((n * n * n - 3 * n * n + 11 * n + 30) / 3));
+        let n: i128 = -1_000_000_000_000;
+        assert_eq!(table.extrapolate_backwards(1_000_000_000_000), Ok((n * n * n - 3 * n * n + 11 * n + 30) / 3));
+        assert_eq!(DifferenceTable::from_sequence(&[7, 7]).unwrap().extrapolate_forwards(u64::MAX), Ok(7));
+    }
+
+    #[test]
+    fn sequences_without_a_zero_row_are_rejected() {
+        assert_eq!(DifferenceTable::from_sequence(&[]).err(), Some(ExtrapolationError::EmptySequence));
+        assert_eq!(DifferenceTable::from_sequence(&[1, 2, 4, 8]).err(), Some(ExtrapolationError::NoZeroRow));
+    }
+
+    #[test]
+    fn overflows_are_reported() {
+        let table = DifferenceTable::from_sequence(&[-i64::MAX, 0, i64::MAX]).unwrap();
+        assert_eq!(table.extrapolate_forwards(u64::MAX), Ok(i64::MAX as i128 * (u64::MAX as i128 + 1)));
+        // p(n) = n^2 goes past i128 around n = 1.3 * 10^19
+        let table = DifferenceTable::from_sequence(&[0, 1, 4, 9]).unwrap();
+        assert_eq!(table.extrapolate_forwards(u64::MAX), Err(ExtrapolationError::Overflow));
+        assert_eq!(table.extrapolate_backwards(u64::MAX), Err(ExtrapolationError::Overflow));
+    }
+
+    #[test]
+    fn coefficients_give_back_the_sequence() {
+        let table = DifferenceTable::from_sequence(&[10, 13, 16, 21, 30, 45]).unwrap();
+        let third = |numerator: i128| Rational::new(numerator, 3).unwrap();
+        assert_eq!(table.coefficients(), Ok(vec![Rational::from_integer(10), third(11), Rational::from_integer(-1), third(1)]));
+    }
+}
@@ -1,56 +1,86 @@
 use std::env;
 use std::fs;
 
+use difference_table::{DifferenceTable, ExtrapolationError};
+use rational::Rational;
+
+mod difference_table;
+mod rational;
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let contents: String =
         fs::read_to_string(&args[1])
             .expect("should be able to read the file");
     let version: u64 = args[2].parse().expect("Should have a problem version");
+    let steps: u64 = parse_flag(&args, "--steps").unwrap_or(1);
+
+    let sequences: Vec<Vec<i64>> = contents.trim().split('\n').map(parse_line).collect();
+    let tables: Vec<Result<DifferenceTable, ExtrapolationError>> =
+        sequences.iter().map(|sequence| DifferenceTable::from_sequence(sequence)).collect();
+
+    if has_flag(&args, "--coefficients") {
+        for (index, table) in tables.iter().enumerate() {
+            match table.as_ref().map_err(|error| *error).and_then(|table| table.coefficients()) {
+                Ok(coefficients) => println!("Sequence {}: p(n) = {}", index + 1, polynomial_to_string(&coefficients)),
+                Err(error) => println!("Sequence {}: {error}", index + 1)
+            }
+        }
+    }
+
+    let extrapolated: Vec<Result<i128, ExtrapolationError>> =
+        tables.iter()
+            .map(|table| match (version, table) {
+                (_, Err(error)) => Err(*error),
+                (1, Ok(table)) => table.extrapolate_forwards(steps),
+                (2, Ok(table)) => table.extrapolate_backwards(steps),
+                _ => panic!("Unknown version {version}")
+            })
+            .collect();
 
-    let sequences: Vec<Vec<i32>> = contents.trim().split("\n").map(parse_line).collect();
-
-    match version {
-        1 => {
-            let result: i32 = sequences.iter().map(extrapolate_forwards).sum();
-            println!("Result is {result}");
-        },
-        2 => {
-            let result: i32 = sequences.iter().map(extrapolate_backwards).sum();
-            println!("Result is {result}");
-        },
-        _ => panic!("Unknown version {version}")
-    };
+    let mut failures: usize = 0;
+    for (index, value) in extrapolated.iter().enumerate() {
+        if let Err(error) = value {
+            eprintln!("Could not extrapolate sequence {}: {error}", index + 1);
+            failures += 1;
+        }
+    }
+    if failures > 0 {
+        println!("Could not extrapolate {failures} out of {} sequences", sequences.len());
+        return;
+    }
+    let result: Option<i128> =
+        extrapolated.iter().try_fold(0_i128, |acc, value| acc.checked_add(*value.as_ref().unwrap()));
+    match result {
+        Some(result) => println!("Result is {result}"),
+        None => println!("Result overflowed")
+    }
 }
 
-fn parse_line(line: &str) -> Vec<i32> {
+fn parse_line(line: &str) -> Vec<i64> {
     line.split(' ').map(|x| x.parse().expect("Should be an integer")).collect()
 }
 
-fn extrapolate_forwards(sequence: &Vec<i32>) -> i32 {
-    let all_zeroes = sequence.iter().all(|x| *x == 0);
-    if all_zeroes {
-        return 0;
-    }
-    let mut diff_sequence: Vec<i32> = Vec::new();
-    for i in 1..sequence.len() {
-        let diff = sequence[i] - sequence[i - 1];
-        diff_sequence.push(diff);
-    }
-    let extrapolated_diff: i32 = extrapolate_forwards(&diff_sequence);
-    sequence.last().unwrap() + extrapolated_diff
+fn parse_flag<T: std::str::FromStr>(args: &[String], name: &str) -> Option<T> {
+    args.iter()
+        .position(|arg| arg == name)
+        .map(|index| args.get(index + 1).expect("Flag should have a value"))
+        .map(|value| value.parse().unwrap_or_else(|_| panic!("Invalid value {value} for {name}")))
 }
 
-fn extrapolate_backwards(sequence: &Vec<i32>) -> i32 {
-    let all_zeroes = sequence.iter().all(|x| *x == 0);
-    if all_zeroes {
-        return 0;
-    }
-    let mut diff_sequence: Vec<i32> = Vec::new();
-    for i in 1..sequence.len() {
-        let diff = sequence[i] - sequence[i - 1];
-        diff_sequence.push(diff);
-    }
-    let extrapolated_diff: i32 = extrapolate_backwards(&diff_sequence);
-    sequence.first().unwrap() - extrapolated_diff
-}
\ No newline at end of file
+fn has_flag(args: &[String], name: &str) -> bool { args.iter().any(|arg| arg == name) }
+
+fn polynomial_to_string(coefficients: &[Rational]) -> String {
+    let terms: Vec<String> =
+        coefficients.iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, coefficient)| !coefficient.is_zero())
+            .map(|(power, coefficient)| match power {
+                0 => format!("{coefficient}"),
+                1 => format!("({coefficient})n"),
+                _ => format!("({coefficient})n^{power}")
+            })
+            .collect();
+    if terms.is_empty() { String::from("0") } else { terms.join(" + ") }
+}
@@ -0,0 +1,98 @@
+use std::fmt;
+
+// Exact fraction with a positive denominator, always kept in lowest terms
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational { numerator: 0, denominator: 1 };
+
+    pub fn new(numerator: i128, denominator: i128) -> Option<Rational> {
+        if denominator == 0 {
+            return None;
+        }
+        let divisor = gcd(numerator, denominator);
+        let sign: i128 = if denominator < 0 { -1 } else { 1 };
+        Some(Rational {
+            numerator: (numerator / divisor).checked_mul(sign)?,
+            denominator: (denominator / divisor).checked_mul(sign)?
+        })
+    }
+
+    pub fn from_integer(value: i128) -> Rational { Rational { numerator: value, denominator: 1 } }
+
+    pub fn is_zero(&self) -> bool { self.numerator == 0 }
+
+    pub fn checked_add(&self, other: &Rational) -> Option<Rational> {
+        let divisor = gcd(self.denominator, other.denominator);
+        let left = self.numerator.checked_mul(other.denominator / divisor)?;
+        let right = other.numerator.checked_mul(self.denominator / divisor)?;
+        let denominator = (self.denominator / divisor).checked_mul(other.denominator)?;
+        Self::new(left.checked_add(right)?, denominator)
+    }
+
+    pub fn checked_mul(&self, other: &Rational) -> Option<Rational> {
+        // Cross-reduce first so that intermediate products stay as small as possible
+        let divisor1 = gcd(self.numerator, other.denominator);
+        let divisor2 = gcd(other.numerator, self.denominator);
+        let numerator = (self.numerator / divisor1).checked_mul(other.numerator / divisor2)?;
+        let denominator = (self.denominator / divisor2).checked_mul(other.denominator / divisor1)?;
+        Self::new(numerator, denominator)
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let mut a = a.unsigned_abs();
+    let mut b = b.unsigned_abs();
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    // gcd(0, 0) would be 0, which is never a valid divisor
+    if a == 0 { 1 } else { a as i128 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fractions_are_kept_in_lowest_terms() {
+        assert_eq!(Rational::new(6, -4), Some(Rational { numerator: -3, denominator: 2 }));
+        assert_eq!(Rational::new(0, 5), Some(Rational::ZERO));
+        assert_eq!(Rational::new(1, 0), None);
+        assert_eq!(Rational::new(-4, 2).unwrap().to_string(), "-2");
+        assert_eq!(Rational::new(3, 9).unwrap().to_string(), "1/3");
+    }
+
+    #[test]
+    fn arithmetic_is_exact() {
+        let half = Rational::new(1, 2).unwrap();
+        let third = Rational::new(1, 3).unwrap();
+        assert_eq!(half.checked_add(&third), Rational::new(5, 6));
+        assert_eq!(half.checked_mul(&third), Rational::new(1, 6));
+        assert_eq!(third.checked_add(&Rational::new(-1, 3).unwrap()), Some(Rational::ZERO));
+        assert_eq!(Rational::new(2, 3).unwrap().checked_mul(&Rational::new(3, 4).unwrap()), Rational::new(1, 2));
+    }
+
+    #[test]
+    fn overflows_give_none() {
+        let big = Rational::from_integer(i128::MAX);
+        assert_eq!(big.checked_add(&Rational::from_integer(1)), None);
+        assert_eq!(big.checked_mul(&Rational::from_integer(2)), None);
+        // Cross-reducing keeps this product in range
+        assert_eq!(big.checked_mul(&Rational::new(1, i128::MAX).unwrap()), Some(Rational::from_integer(1)));
+    }
+}
@@ -0,0 +1,214 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::mem;
+use std::str::FromStr;
+
+// Ways of finding where the sequence start, step(start), step(step(start)), ... starts repeating.
+// The hash map remembers every state it has seen, while Floyd and Brent only keep a couple of
+// states around and call the step function more often instead
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Strategy { HashMap, Floyd, Brent }
+
+// The state after `offset` steps is the first one that repeats, and it does every `period` steps
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    pub offset: u64,
+    pub period: u64
+}
+
+// Work done to find a cycle. Memory is counted in states, since how much each of them owns on the
+// heap isn't known here; `state_size` is just the size of the state itself
+#[derive(Copy, Clone, Debug)]
+pub struct Usage {
+    pub steps: u64,
+    pub peak_states: usize,
+    pub state_size: usize
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Strategy, String> {
+        match text {
+            "hashmap" => Ok(Strategy::HashMap),
+            "floyd" => Ok(Strategy::Floyd),
+            "brent" => Ok(Strategy::Brent),
+            _ => Err(format!("Unknown cycle detection strategy {text}"))
+        }
+    }
+}
+
+impl Cycle {
+    // Number of steps that lead to the same state as taking the given number of steps, but
+    // without going around the cycle more than once
+    pub fn reduce(&self, steps: u64) -> u64 {
+        if steps < self.offset {
+            return steps;
+        }
+        self.offset + (steps - self.offset) % self.period
+    }
+
+    pub fn state_after<S: Clone, F: Fn(&S) -> S>(&self, start: &S, step: F, steps: u64) -> S {
+        (0..self.reduce(steps)).fold(start.clone(), |state, _| step(&state))
+    }
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} steps, at most {} states held at once ({} bytes each, not counting the heap)",
+            self.steps, self.peak_states, self.state_size)
+    }
+}
+
+pub fn find_cycle<S, F>(start: &S, step: F, strategy: Strategy) -> (Cycle, Usage)
+where
+    S: Clone + Eq + Hash,
+    F: Fn(&S) -> S
+{
+    let steps: Cell<u64> = Cell::new(0);
+    let counted_step = |state: &S| {
+        steps.set(steps.get() + 1);
+        step(state)
+    };
+    let (cycle, peak_states) = match strategy {
+        Strategy::HashMap => find_with_hash_map(start, counted_step),
+        Strategy::Floyd => (find_with_floyd(start, counted_step), 3),
+        Strategy::Brent => (find_with_brent(start, counted_step), 3)
+    };
+    (cycle, Usage { steps: steps.get(), peak_states, state_size: mem::size_of::<S>() })
+}
+
+fn find_with_hash_map<S: Clone + Eq + Hash, F: Fn(&S) -> S>(start: &S, step: F) -> (Cycle, usize) {
+    let mut seen: HashMap<S, u64> = HashMap::new();
+    let mut current: S = start.clone();
+    for index in 0_u64.. {
+        if let Some(&offset) = seen.get(&current) {
+            return (Cycle { offset, period: index - offset }, seen.len() + 1);
+        }
+        let next = step(&current);
+        seen.insert(current, index);
+        current = next;
+    }
+    panic!("Escaped an infinite loop!");
+}
+
+// The hare moves twice as fast as the tortoise, so they meet somewhere inside the cycle. From
+// there, a tortoise restarted from the start meets the hare right at the start of the cycle
+fn find_with_floyd<S: Clone + Eq, F: Fn(&S) -> S>(start: &S, step: F) -> Cycle {
+    let mut tortoise: S = step(start);
+    let mut hare: S = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&step(&hare));
+    }
+
+    let mut offset: u64 = 0;
+    tortoise = start.clone();
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        offset += 1;
+    }
+
+    let mut period: u64 = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        period += 1;
+    }
+    Cycle { offset, period }
+}
+
+// The tortoise teleports to the hare at every power of two, which finds the period directly. A
+// hare that starts a period ahead then meets the tortoise at the start of the cycle
+fn find_with_brent<S: Clone + Eq, F: Fn(&S) -> S>(start: &S, step: F) -> Cycle {
+    let mut power: u64 = 1;
+    let mut period: u64 = 1;
+    let mut tortoise: S = start.clone();
+    let mut hare: S = step(start);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+
+    tortoise = start.clone();
+    hare = (0..period).fold(start.clone(), |state, _| step(&state));
+    let mut offset: u64 = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        offset += 1;
+    }
+    Cycle { offset, period }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STRATEGIES: [Strategy; 3] = [Strategy::HashMap, Strategy::Floyd, Strategy::Brent];
+
+    // Counts up from 0, and goes back to `offset` after `offset + period - 1`
+    fn rho(offset: u64, period: u64) -> impl Fn(&u64) -> u64 {
+        move |&state| if state + 1 < offset + period { state + 1 } else { offset }
+    }
+
+    #[test]
+    fn every_strategy_finds_the_known_cycles() {
+        for (offset, period) in [(0, 1), (0, 6), (1, 1), (4, 1), (3, 7), (10, 2), (1, 64), (37, 100)] {
+            for strategy in STRATEGIES {
+                let (cycle, _) = find_cycle(&0, rho(offset, period), strategy);
+                assert_eq!(cycle, Cycle { offset, period }, "{strategy:?} on offset {offset} and period {period}");
+            }
+        }
+    }
+
+    #[test]
+    fn strategies_agree_on_an_irregular_sequence() {
+        for modulus in [17_u64, 255, 1009, 65_537] {
+            for start in [0, 1, 2, 12] {
+                let step = move |&state: &u64| (state * state + 1) % modulus;
+                let cycles: Vec<Cycle> = STRATEGIES.iter().map(|&strategy| find_cycle(&start, step, strategy).0).collect();
+                assert!(cycles.iter().all(|cycle| *cycle == cycles[0]), "{cycles:?} from {start} mod {modulus}");
+                let cycle = cycles[0];
+                let repeated = cycle.state_after(&start, step, cycle.offset);
+                assert_eq!(cycle.state_after(&start, step, cycle.offset + cycle.period), repeated);
+            }
+        }
+    }
+
+    #[test]
+    fn reduced_steps_stay_within_the_first_loop() {
+        let cycle = Cycle { offset: 3, period: 4 };
+        let reduced: Vec<u64> = (0..12).map(|steps| cycle.reduce(steps)).collect();
+        assert_eq!(reduced, vec![0, 1, 2, 3, 4, 5, 6, 3, 4, 5, 6, 3]);
+        assert_eq!(Cycle { offset: 0, period: 1 }.reduce(1_000_000), 0);
+    }
+
+    #[test]
+    fn states_after_many_steps_match_stepping_through() {
+        let step = rho(5, 9);
+        let cycle = Cycle { offset: 5, period: 9 };
+        let mut state: u64 = 0;
+        for steps in 0..100 {
+            assert_eq!(cycle.state_after(&0, &step, steps), state);
+            state = step(&state);
+        }
+        assert_eq!(cycle.state_after(&0, &step, u64::MAX), 5 + (u64::MAX - 5) % 9);
+    }
+
+    #[test]
+    fn strategies_are_parsed_by_name() {
+        assert_eq!("floyd".parse(), Ok(Strategy::Floyd));
+        assert_eq!("brent".parse(), Ok(Strategy::Brent));
+        assert_eq!("hashmap".parse(), Ok(Strategy::HashMap));
+        assert!("tortoise".parse::<Strategy>().is_err());
+    }
+}
@@ -3,12 +3,14 @@ mod vector;
 mod line;
 mod ray;
 mod bounds;
+mod grid_dimensions;
 
 pub use self::point::Point;
 pub use self::vector::Vector;
 pub use self::line::Line;
 pub use self::ray::Ray;
 pub use self::bounds::Bounds;
+pub use self::grid_dimensions::GridDimensions;
 
 fn truncate(value: f32, decimal_places: u32) -> u64 {
     if decimal_places == 0 {
@@ -29,6 +29,17 @@ impl Bounds {
       let withing_y_bounds = self.bottom_left.1 <= p.1 && p.1 <= self.top_right.1;
       within_x_bounds && withing_y_bounds
   }
+
+  pub fn intersects(&self, other: &Bounds) -> bool {
+      self.bottom_left.0 <= other.top_right.0 && other.bottom_left.0 <= self.top_right.0
+          && self.bottom_left.1 <= other.top_right.1 && other.bottom_left.1 <= self.top_right.1
+  }
+
+  // Distance from the point to the closest point of the bounds, which is 0 for points inside them
+  pub fn distance_to(&self, p: &Point) -> f32 {
+      let closest = Point(p.0.clamp(self.bottom_left.0, self.top_right.0), p.1.clamp(self.bottom_left.1, self.top_right.1));
+      closest.distance(p)
+  }
 }
 
 impl Eq for Bounds {}
\ No newline at end of file
@@ -0,0 +1,52 @@
+use crate::geometry::{Bounds, Point};
+
+// Size of the puzzle grid in tiles. The tile in the first column of the last line is at the origin,
+// so x goes along the lines and y goes up through them
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GridDimensions {
+    pub width: usize,
+    pub height: usize
+}
+
+impl GridDimensions {
+    pub fn from_lines(lines: &[&str]) -> Result<GridDimensions, String> {
+        let width: usize = lines.first().map_or(0, |line| line.chars().count());
+        if width == 0 {
+            return Err(String::from("The grid is empty"));
+        }
+        for (index, line) in lines.iter().enumerate() {
+            let line_width = line.chars().count();
+            if line_width != width {
+                return Err(format!("Line {} is {line_width} tiles wide, but line 1 is {width} tiles wide", index + 1));
+            }
+        }
+        Ok(GridDimensions { width, height: lines.len() })
+    }
+
+    pub fn top_right_tile(&self) -> Point { Point(self.width as f32 - 1.0, self.height as f32 - 1.0) }
+
+    pub fn bounds(&self) -> Bounds { Bounds::from_origin(&self.top_right_tile()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width_is_along_the_lines() {
+        let dimensions = GridDimensions::from_lines(&["abcd", "efgh"]).unwrap();
+        assert_eq!(dimensions, GridDimensions { width: 4, height: 2 });
+        assert_eq!(dimensions.top_right_tile(), Point(3.0, 1.0));
+        assert!(dimensions.bounds().contains(&Point(3.0, 0.0)));
+        assert!(!dimensions.bounds().contains(&Point(0.0, 3.0)));
+    }
+
+    #[test]
+    fn ragged_and_empty_grids_are_rejected() {
+        assert_eq!(
+            GridDimensions::from_lines(&["...", "...", "....", ".."]),
+            Err(String::from("Line 3 is 4 tiles wide, but line 1 is 3 tiles wide")));
+        assert_eq!(GridDimensions::from_lines(&[]), Err(String::from("The grid is empty")));
+        assert_eq!(GridDimensions::from_lines(&[""]), Err(String::from("The grid is empty")));
+    }
+}
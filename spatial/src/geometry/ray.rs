@@ -7,7 +7,7 @@ pub struct Ray(pub Point, pub Vector);
 
 impl Ray {
   pub fn hits(&self, target: &Point) -> bool {
-      if self.0.distance(&target) <= EPSILON {
+      if self.0.distance(target) <= EPSILON {
           // target is ray origin - checks below depend on diff vector and would fail because it would be Vector(0, 0)
           return true;
       }
@@ -48,19 +48,34 @@ impl Ray {
   }
 
   pub fn intersect_bounds(&self, target: &Bounds) -> Option<Point> {
-      let side_lines = vec![
+      self.side_intersections(target)
+          .reduce(|intersection1, intersection2| {
+              let distance1: f32 = self.0.distance(&intersection1);
+              let distance2: f32 = self.0.distance(&intersection2);
+              if distance1 <= distance2 { intersection1 } else { intersection2 }
+          })
+  }
+
+  // Point where the ray leaves the bounds, which is the ray origin itself when it starts on a side
+  // and points away
+  pub fn exit_bounds(&self, target: &Bounds) -> Option<Point> {
+      self.side_intersections(target)
+          .reduce(|intersection1, intersection2| {
+              let distance1: f32 = self.0.distance(&intersection1);
+              let distance2: f32 = self.0.distance(&intersection2);
+              if distance1 >= distance2 { intersection1 } else { intersection2 }
+          })
+  }
+
+  fn side_intersections<'a>(&'a self, target: &'a Bounds) -> impl Iterator<Item = Point> + 'a {
+      [
           Line(target.top_right, Vector::UP),
           Line(target.top_right, Vector::RIGHT),
           Line(target.bottom_left, Vector::DOWN),
           Line(target.bottom_left, Vector::LEFT)
-      ];
-      side_lines.iter()
+      ]
+          .into_iter()
           .filter_map(|side_line| self.intersect_line(&side_line))
           .filter(|intersection| target.contains(intersection))
-          .reduce(|intersection1, intersection2| {
-              let distance1: f32 = self.0.distance(&intersection1);
-              let distance2: f32 = self.0.distance(&intersection2);
-              if distance1 <= distance2 { intersection1 } else { intersection2 }
-          })
   }
 }
\ No newline at end of file
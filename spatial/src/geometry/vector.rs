@@ -33,6 +33,7 @@ impl AddAssign<Vector> for Vector {
 
 impl Sub<Vector> for Vector {
     type Output = Vector;
+    #[allow(clippy::suspicious_arithmetic_impl)]
     fn sub(self, rhs: Vector) -> Vector { self + rhs.inverse() }
 }
 
@@ -0,0 +1,6 @@
+// Floating point geometry on the puzzle grids, and quadtrees to look items up by position
+pub mod geometry;
+pub mod quad_tree;
+
+// Tolerance of the floating point comparisons
+pub const EPSILON: f32 = 1e-4;
@@ -0,0 +1,18 @@
+mod node;
+#[allow(clippy::module_inception)]
+mod quad_tree;
+mod arena_quad_tree;
+mod positioned_2d;
+mod ray_order;
+
+use crate::geometry::Ray;
+
+pub use self::quad_tree::QuadTree;
+pub use self::arena_quad_tree::ArenaQuadTree;
+pub use self::positioned_2d::Positioned2D;
+pub use self::node::Quadrant;
+
+// Anything that can find the closest item hit by a ray
+pub trait SpatialIndex<T> {
+    fn intersect(&self, ray: &Ray, ignore_ray_origin: bool) -> Option<&T>;
+}
@@ -0,0 +1,397 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
+use std::str::FromStr;
+
+use crate::EPSILON;
+use crate::geometry::{Point, Ray, Bounds};
+use super::node::{Quadrant, MAX_DEPTH};
+use super::{Positioned2D, SpatialIndex};
+use super::ray_order::{cmp_closest, to_priority};
+
+// Quadtree that owns its items. Items and nodes live in two vectors and refer to each other by
+// index, so indices stay valid as the tree grows and the whole tree can be kept around, or
+// written to disk and read back
+#[derive(Debug)]
+pub struct ArenaQuadTree<T: Positioned2D> {
+    items: Vec<T>,
+    nodes: Vec<ArenaNode>,
+    max_fill: usize
+}
+
+#[derive(Clone, Debug)]
+struct ArenaNode {
+    bounds: Bounds,
+    content: ArenaContent,
+    depth: u32
+}
+
+#[derive(Clone, Debug)]
+enum ArenaContent {
+    // Indices into the items
+    Items(Vec<u32>),
+    // Indices into the nodes, in the same order as Quadrant::ALL
+    Children([u32; 4])
+}
+
+impl <T: Positioned2D> ArenaQuadTree<T> {
+    const ROOT: u32 = 0;
+
+    pub fn from_bulk(items: Vec<T>, max_fill: usize) -> ArenaQuadTree<T> {
+        let bounds = bounding_box(items.iter().map(|item| item.position()));
+        let mut tree = ArenaQuadTree {
+            items: Vec::with_capacity(items.len()),
+            nodes: vec![ArenaNode { bounds, content: ArenaContent::Items(Vec::new()), depth: 0 }],
+            max_fill
+        };
+        items.into_iter().for_each(|item| { tree.insert(item); });
+        tree
+    }
+
+    // Returns the index of the item, or None if it is outside of the bounds of the tree
+    pub fn insert(&mut self, item: T) -> Option<u32> {
+        if !self.nodes[Self::ROOT as usize].bounds.contains(item.position()) {
+            return None;
+        }
+        let item_id = self.items.len() as u32;
+        self.items.push(item);
+        self.insert_into(Self::ROOT, item_id);
+        Some(item_id)
+    }
+
+    fn insert_into(&mut self, node_id: u32, item_id: u32) {
+        let position = *self.items[item_id as usize].position();
+        let node: &mut ArenaNode = &mut self.nodes[node_id as usize];
+        match &mut node.content {
+            ArenaContent::Items(items) if items.len() < self.max_fill || node.depth >= MAX_DEPTH => items.push(item_id),
+            ArenaContent::Items(_) => {
+                self.split_node(node_id);
+                self.insert_into(node_id, item_id);
+            }
+            ArenaContent::Children(children) => {
+                let child_id = children[Quadrant::of(&node.bounds, &position) as usize];
+                self.insert_into(child_id, item_id);
+            }
+        }
+    }
+
+    fn split_node(&mut self, node_id: u32) {
+        let ArenaNode { bounds, depth, .. } = self.nodes[node_id as usize];
+        let first_child_id = self.nodes.len() as u32;
+        let children: [u32; 4] = [0, 1, 2, 3].map(|offset| first_child_id + offset);
+        let content = std::mem::replace(&mut self.nodes[node_id as usize].content, ArenaContent::Children(children));
+        let items: Vec<u32> = match content {
+            ArenaContent::Items(items) => items,
+            ArenaContent::Children(_) => panic!("Trying to split a non-leaf node")
+        };
+        for (quadrant, child_bounds) in Quadrant::ALL.into_iter().zip(Quadrant::split(&bounds)) {
+            let child_items: Vec<u32> =
+                items.iter()
+                    .filter(|&&item_id| Quadrant::of(&bounds, self.items[item_id as usize].position()) == quadrant)
+                    .cloned()
+                    .collect();
+            let child = ArenaNode { bounds: child_bounds, content: ArenaContent::Items(child_items), depth: depth + 1 };
+            self.nodes.push(child);
+        }
+    }
+
+    // Checks that every item is stored exactly once, in the leaf its position belongs to, and
+    // that every node is the child of exactly one other node
+    pub fn check_invariants(&self) -> Result<(), String> {
+        check_structure(&self.nodes)?;
+        let mut item_counts: Vec<usize> = vec![0; self.items.len()];
+        let mut pending: Vec<u32> = vec![Self::ROOT];
+        while let Some(node_id) = pending.pop() {
+            let node: &ArenaNode = &self.nodes[node_id as usize];
+            match &node.content {
+                ArenaContent::Items(items) => {
+                    if items.len() > self.max_fill && node.depth < MAX_DEPTH {
+                        return Err(format!("Node {node_id} holds {} items above the maximum depth", items.len()));
+                    }
+                    for &item_id in items.iter() {
+                        let item: &T = self.items.get(item_id as usize).ok_or(format!("Item {item_id} is missing"))?;
+                        let expected_id = self.leaf_for(item.position());
+                        if expected_id != node_id {
+                            return Err(format!("Item at {:?} is in node {node_id} instead of {expected_id}", item.position()));
+                        }
+                        item_counts[item_id as usize] += 1;
+                    }
+                }
+                ArenaContent::Children(children) => {
+                    for (&child_id, child_bounds) in children.iter().zip(Quadrant::split(&node.bounds)) {
+                        let child: &ArenaNode = &self.nodes[child_id as usize];
+                        if child.depth != node.depth + 1 || child.bounds != child_bounds {
+                            return Err(format!("Node {child_id} doesn't match its parent {node_id}"));
+                        }
+                        pending.push(child_id);
+                    }
+                }
+            }
+        }
+        if let Some(item_id) = item_counts.iter().position(|&count| count != 1) {
+            return Err(format!("Item {item_id} is stored {} times", item_counts[item_id]));
+        }
+        Ok(())
+    }
+
+    // Leaf that an item at the given position belongs to
+    fn leaf_for(&self, position: &Point) -> u32 {
+        let mut node_id = Self::ROOT;
+        loop {
+            let node: &ArenaNode = &self.nodes[node_id as usize];
+            match &node.content {
+                ArenaContent::Items(_) => return node_id,
+                ArenaContent::Children(children) => node_id = children[Quadrant::of(&node.bounds, position) as usize]
+            }
+        }
+    }
+
+    // Same best-first search as QuadTree::intersect
+    pub fn intersect(&self, ray: &Ray, ignore_ray_origin: bool) -> Option<&T> {
+        let mut queue: BTreeMap<u64, Vec<u32>> = BTreeMap::from([(0_u64, vec![Self::ROOT])]);
+        let mut best_hit: Option<(u64, &T)> = None;
+
+        while let Some((priority, closest_nodes)) = queue.pop_first() {
+            if best_hit.is_some_and(|(best_priority, _)| best_priority < priority) {
+                break;
+            }
+            for node_id in closest_nodes {
+                match &self.nodes[node_id as usize].content {
+                    ArenaContent::Items(items) => {
+                        let hit =
+                            items.iter()
+                                .map(|&item_id| &self.items[item_id as usize])
+                                .filter(|item| ray.hits(item.position()))
+                                .filter(|item| !ignore_ray_origin || item.position().distance(&ray.0) > EPSILON)
+                                .min_by(|item1, item2| cmp_closest(ray, *item1, *item2))
+                                .map(|hit| (to_priority(hit.position().distance(&ray.0)), hit));
+                        if hit.is_some_and(|(hit_priority, _)| best_hit.is_none_or(|(best_priority, _)| hit_priority < best_priority)) {
+                            best_hit = hit;
+                        }
+                    }
+                    ArenaContent::Children(children) => {
+                        for &child_id in children {
+                            if let Some(intersection) = ray.intersect_bounds(&self.nodes[child_id as usize].bounds) {
+                                queue.entry(to_priority(intersection.distance(&ray.0))).or_default().push(child_id);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        best_hit.map(|(_, hit)| hit)
+    }
+}
+
+impl <T: Positioned2D> SpatialIndex<T> for ArenaQuadTree<T> {
+    fn intersect(&self, ray: &Ray, ignore_ray_origin: bool) -> Option<&T> {
+        ArenaQuadTree::intersect(self, ray, ignore_ray_origin)
+    }
+}
+
+// Line based text format. The header is followed by one line per item and one line per node,
+// both in index order:
+//   quadtree <max fill> <item count> <node count>
+//   item <item>
+//   leaf <min x> <min y> <max x> <max y> <item index>...
+//   split <min x> <min y> <max x> <max y> <top left> <top right> <bottom right> <bottom left>
+impl <T: Positioned2D + Display + FromStr> ArenaQuadTree<T> {
+    pub fn to_text(&self) -> String {
+        let mut text = format!("quadtree {} {} {}\n", self.max_fill, self.items.len(), self.nodes.len());
+        for item in self.items.iter() {
+            writeln!(text, "item {item}").unwrap();
+        }
+        for node in self.nodes.iter() {
+            let Bounds { bottom_left: Point(min_x, min_y), top_right: Point(max_x, max_y) } = node.bounds;
+            let (kind, ids) = match &node.content {
+                ArenaContent::Items(items) => ("leaf", items.as_slice()),
+                ArenaContent::Children(children) => ("split", children.as_slice())
+            };
+            write!(text, "{kind} {min_x} {min_y} {max_x} {max_y}").unwrap();
+            for id in ids {
+                write!(text, " {id}").unwrap();
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<ArenaQuadTree<T>, String> {
+        let mut lines = text.lines().enumerate();
+        let header: Vec<usize> = match lines.next().map(|(_, line)| line.split(' ').collect::<Vec<&str>>()) {
+            Some(fields) if fields.len() == 4 && fields[0] == "quadtree" =>
+                fields[1..].iter().map(|field| parse_field(field, 1)).collect::<Result<_, _>>()?,
+            _ => return Err(String::from("Line 1: expected a quadtree header"))
+        };
+        let (max_fill, item_count, node_count) = (header[0], header[1], header[2]);
+
+        let mut items: Vec<T> = Vec::with_capacity(item_count);
+        let mut nodes: Vec<ArenaNode> = Vec::with_capacity(node_count);
+        for (index, line) in lines {
+            let line_number = index + 1;
+            let (kind, rest) = line.split_once(' ').ok_or(format!("Line {line_number}: expected a record"))?;
+            if kind == "item" {
+                let item = rest.parse::<T>().map_err(|_| format!("Line {line_number}: invalid item {rest}"))?;
+                items.push(item);
+                continue;
+            }
+            let fields: Vec<&str> = rest.split(' ').collect();
+            if fields.len() < 4 {
+                return Err(format!("Line {line_number}: expected the node bounds"));
+            }
+            let corners: Vec<f32> = fields[..4].iter().map(|field| parse_field(field, line_number)).collect::<Result<_, _>>()?;
+            let ids: Vec<u32> = fields[4..].iter().map(|field| parse_field(field, line_number)).collect::<Result<_, _>>()?;
+            let content = match (kind, ids.len()) {
+                ("leaf", _) => ArenaContent::Items(ids),
+                ("split", 4) => ArenaContent::Children([ids[0], ids[1], ids[2], ids[3]]),
+                _ => return Err(format!("Line {line_number}: invalid node"))
+            };
+            let bounds = Bounds::from_two(&Point(corners[0], corners[1]), &Point(corners[2], corners[3]));
+            nodes.push(ArenaNode { bounds, content, depth: 0 });
+        }
+
+        if items.len() != item_count || nodes.len() != node_count {
+            return Err(format!("Expected {item_count} items and {node_count} nodes, found {} and {}", items.len(), nodes.len()));
+        }
+        // Depths aren't stored, since they follow from the structure. Children always come after
+        // their parents, so a single pass sets them
+        check_structure(&nodes)?;
+        for node_id in 0..node_count {
+            if let ArenaContent::Children(children) = nodes[node_id].content {
+                for child_id in children {
+                    nodes[child_id as usize].depth = nodes[node_id].depth + 1;
+                }
+            }
+        }
+        let tree = ArenaQuadTree { items, nodes, max_fill };
+        tree.check_invariants()?;
+        Ok(tree)
+    }
+}
+
+fn parse_field<F: FromStr>(field: &str, line_number: usize) -> Result<F, String> {
+    field.parse().map_err(|_| format!("Line {line_number}: invalid number {field}"))
+}
+
+// Every node but the root has exactly one parent, which comes before it. The links can then
+// only form a tree, so walking down from the root always ends
+fn check_structure(nodes: &[ArenaNode]) -> Result<(), String> {
+    let mut parent_counts: Vec<usize> = vec![0; nodes.len()];
+    for (node_id, node) in nodes.iter().enumerate() {
+        if let ArenaContent::Children(children) = node.content {
+            for child_id in children {
+                if child_id as usize >= nodes.len() {
+                    return Err(format!("Node {child_id} is missing"));
+                }
+                if child_id as usize <= node_id {
+                    return Err(format!("Node {child_id} comes before its parent {node_id}"));
+                }
+                parent_counts[child_id as usize] += 1;
+            }
+        }
+    }
+    if let Some(node_id) = parent_counts.iter().skip(1).position(|&count| count > 1) {
+        return Err(format!("Node {} has several parents", node_id + 1));
+    }
+    if let Some(node_id) = parent_counts.iter().skip(1).position(|&count| count == 0) {
+        return Err(format!("Node {} is unreachable", node_id + 1));
+    }
+    Ok(())
+}
+
+fn bounding_box<'a>(points: impl Iterator<Item = &'a Point> + Clone) -> Bounds {
+    let min_x: f32 = points.clone().fold(f32::MAX, |acc, p| acc.min(p.0));
+    let max_x: f32 = points.clone().fold(f32::MIN, |acc, p| acc.max(p.0));
+    let min_y: f32 = points.clone().fold(f32::MAX, |acc, p| acc.min(p.1));
+    let max_y: f32 = points.fold(f32::MIN, |acc, p| acc.max(p.1));
+    Bounds::from_two(&Point(min_x, min_y), &Point(max_x, max_y))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Item(Point);
+
+    impl Positioned2D for Item {
+        fn position(&self) -> &Point { &self.0 }
+    }
+
+    impl fmt::Display for Item {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{} {}", self.0.0, self.0.1) }
+    }
+
+    impl FromStr for Item {
+        type Err = String;
+
+        fn from_str(text: &str) -> Result<Item, String> {
+            let (x, y) = text.split_once(' ').ok_or(format!("Expected x and y in {text}"))?;
+            Ok(Item(Point(parse_field(x, 0)?, parse_field(y, 0)?)))
+        }
+    }
+
+    fn grid_items() -> Vec<Item> {
+        (0..=8).flat_map(|x| (0..=8).map(move |y| Item(Point(x as f32, y as f32)))).collect()
+    }
+
+    #[test]
+    fn coincident_items_stop_at_the_maximum_depth() {
+        let mut tree: ArenaQuadTree<Item> = ArenaQuadTree::from_bulk(vec![Item(Point(0.0, 0.0)), Item(Point(8.0, 8.0))], 2);
+        for _ in 0..10 {
+            assert!(tree.insert(Item(Point(3.0, 3.0))).is_some());
+            tree.check_invariants().unwrap();
+        }
+        let deepest = tree.nodes.iter().filter(|node| matches!(node.content, ArenaContent::Items(_))).map(|node| node.depth).max();
+        assert_eq!(deepest, Some(MAX_DEPTH));
+    }
+
+    #[test]
+    fn items_on_split_lines_are_stored_once() {
+        let mut tree: ArenaQuadTree<Item> = ArenaQuadTree::from_bulk(vec![Item(Point(0.0, 0.0)), Item(Point(8.0, 8.0))], 1);
+        for item in grid_items() {
+            assert!(tree.insert(item).is_some());
+            tree.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn saved_trees_load_back_the_same() {
+        let tree: ArenaQuadTree<Item> =
+            ArenaQuadTree::from_bulk(grid_items().into_iter().chain((0..5).map(|_| Item(Point(2.5, 6.0)))).collect(), 2);
+        tree.check_invariants().unwrap();
+        let text = tree.to_text();
+        let loaded: ArenaQuadTree<Item> = ArenaQuadTree::from_text(&text).unwrap();
+        loaded.check_invariants().unwrap();
+        assert_eq!(loaded.to_text(), text);
+        assert_eq!(loaded.items, tree.items);
+        let depths = |tree: &ArenaQuadTree<Item>| tree.nodes.iter().map(|node| node.depth).collect::<Vec<u32>>();
+        assert_eq!(depths(&loaded), depths(&tree));
+        let ray = Ray(Point(-1.0, 4.0), crate::geometry::Vector::RIGHT);
+        assert_eq!(loaded.intersect(&ray, true), tree.intersect(&ray, true));
+    }
+
+    #[test]
+    fn broken_tree_files_are_rejected() {
+        let text = ArenaQuadTree::from_bulk(grid_items(), 2).to_text();
+        let moved_item = text.replacen("item 0 1", "item 7 7", 1);
+        assert!(ArenaQuadTree::<Item>::from_text(&moved_item).is_err());
+        let missing_node: String = text.lines().take(text.lines().count() - 1).map(|line| format!("{line}\n")).collect();
+        assert!(ArenaQuadTree::<Item>::from_text(&missing_node).is_err());
+    }
+
+    #[test]
+    fn cyclic_node_links_are_rejected() {
+        let leaf = |bounds: &str| format!("leaf {bounds}\n");
+        let header = "quadtree 1 0 5\n";
+        // Node 4 points back to the root, and then to itself
+        let text = format!("{header}split 0 0 4 4 1 2 3 4\n{}{}{}split 2 0 4 2 0 0 0 0\n",
+            leaf("0 2 2 4"), leaf("2 2 4 4"), leaf("0 0 2 2"));
+        assert_eq!(ArenaQuadTree::<Item>::from_text(&text).err(), Some(String::from("Node 0 comes before its parent 4")));
+        let text = text.replace("split 2 0 4 2 0 0 0 0", "split 2 0 4 2 4 4 4 4");
+        assert_eq!(ArenaQuadTree::<Item>::from_text(&text).err(), Some(String::from("Node 4 comes before its parent 4")));
+        let shared = format!("{header}split 0 0 4 4 1 2 3 3\n{}{}{}{}", leaf("0 2 2 4"), leaf("2 2 4 4"), leaf("0 0 2 2"), leaf("2 0 4 2"));
+        assert_eq!(ArenaQuadTree::<Item>::from_text(&shared).err(), Some(String::from("Node 3 has several parents")));
+    }
+}
@@ -0,0 +1,75 @@
+use crate::geometry::{Bounds, Point};
+
+use super::Positioned2D;
+
+// Leaves this deep are never split, and hold any number of items instead. Otherwise more than
+// max_fill items at the same position would be split forever
+pub const MAX_DEPTH: u32 = 16;
+
+#[derive(Clone, Debug)]
+pub struct Node<'t, T: Positioned2D> {
+    pub parent_id: Option<u32>,
+    pub content: NodeContent<'t, T>,
+    pub bounds: Bounds,
+    pub depth: u32
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Quadrant { TopLeft, TopRight, BottomRight, BottomLeft }
+
+impl Quadrant {
+    pub const ALL: [Quadrant; 4] = [Quadrant::TopLeft, Quadrant::TopRight, Quadrant::BottomRight, Quadrant::BottomLeft];
+
+    // Quadrant of the bounds the point belongs to. Child bounds are half-open: points on a split
+    // line belong to the quadrant to their right or above them, so each point has exactly one
+    pub fn of(bounds: &Bounds, p: &Point) -> Quadrant {
+        let middle = bounds.bottom_left + ((bounds.top_right - bounds.bottom_left) / 2.0);
+        match (p.0 >= middle.0, p.1 >= middle.1) {
+            (false, true) => Quadrant::TopLeft,
+            (true, true) => Quadrant::TopRight,
+            (true, false) => Quadrant::BottomRight,
+            (false, false) => Quadrant::BottomLeft
+        }
+    }
+
+    // Bounds of the quadrants, in the same order as ALL
+    pub fn split(bounds: &Bounds) -> [Bounds; 4] {
+        let middle = bounds.bottom_left + ((bounds.top_right - bounds.bottom_left) / 2.0);
+        [
+            Bounds::from_two(&Point(bounds.bottom_left.0, bounds.top_right.1), &middle),
+            Bounds::from_two(&middle, &bounds.top_right),
+            Bounds::from_two(&middle, &Point(bounds.top_right.0, bounds.bottom_left.1)),
+            Bounds::from_two(&bounds.bottom_left, &middle)
+        ]
+    }
+}
+
+impl <'t, T: Positioned2D> Node<'t, T> {
+    pub fn child_id(&self, quadrant: Quadrant) -> u32 {
+        match (&self.content, quadrant) {
+            (NodeContent::Items(_), _) => panic!("Leaves have no children"),
+            (NodeContent::Children { top_left_id, .. }, Quadrant::TopLeft) => *top_left_id,
+            (NodeContent::Children { top_right_id, .. }, Quadrant::TopRight) => *top_right_id,
+            (NodeContent::Children { bottom_right_id, .. }, Quadrant::BottomRight) => *bottom_right_id,
+            (NodeContent::Children { bottom_left_id, .. }, Quadrant::BottomLeft) => *bottom_left_id
+        }
+    }
+
+    pub fn get_items(self) -> Vec<&'t T> {
+        match self.content {
+            NodeContent::Children { .. } => panic!("Trying to split a non-leaf node"),
+            NodeContent::Items(items) => items
+        }
+     }
+}
+
+#[derive(Clone, Debug)]
+pub enum NodeContent<'t, T: Positioned2D> {
+    Items(Vec<&'t T>),
+    Children {
+        top_left_id: u32,
+        top_right_id: u32,
+        bottom_right_id: u32,
+        bottom_left_id: u32
+    }
+}
\ No newline at end of file
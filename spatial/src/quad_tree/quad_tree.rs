@@ -0,0 +1,607 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, BTreeMap};
+use std::fmt;
+use std::ptr;
+
+use crate::EPSILON;
+use crate::geometry::{Point, Ray, Bounds};
+use super::node::{Node, NodeContent, Quadrant, MAX_DEPTH};
+use super::{Positioned2D, SpatialIndex};
+use super::ray_order::{cmp_closest, to_priority};
+
+#[derive(Debug)]
+pub struct QuadTree<'t, T: Positioned2D> {
+    nodes: HashMap<u32, Node<'t, T>>,
+    max_fill: usize,
+    next_id: u32,
+    item_count: usize
+}
+
+// Quadrants taken from the root to reach a leaf
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodePath(pub Vec<Quadrant>);
+
+// Depth-first walk over the leaves, yielding every item along with the path to its leaf
+pub struct ItemPaths<'q, 't, T: Positioned2D> {
+    tree: &'q QuadTree<'t, T>,
+    pending: Vec<(u32, NodePath)>,
+    current: Option<(NodePath, std::slice::Iter<'q, &'t T>)>
+}
+
+impl <'t, T: 't + Positioned2D> QuadTree<'t, T> {
+    const ROOT: u32 = 0;
+
+    pub fn from_bulk<'a>(items: &'a [T], max_fill: usize) -> QuadTree<'a, T> {
+        let mut tree = QuadTree {
+            nodes: HashMap::from([(Self::ROOT, Self::init_root(items))]),
+            max_fill,
+            next_id: Self::ROOT + 1,
+            item_count: 0
+        };
+        items.iter().for_each(|item| { tree.insert(item); } );
+        tree
+    }
+
+    fn init_root<'a>(points: &'a [T]) -> Node<'a, T> {
+        let min_x: f32 = points.iter().map(|item| item.position()).fold(f32::MAX, |acc, p| acc.min(p.0));
+        let max_x: f32 = points.iter().map(|item| item.position()).fold(f32::MIN, |acc, p| acc.max(p.0));
+        let min_y: f32 = points.iter().map(|item| item.position()).fold(f32::MAX, |acc, p| acc.min(p.1));
+        let max_y: f32 = points.iter().map(|item| item.position()).fold(f32::MIN, |acc, p| acc.max(p.1));
+        let bounds = Bounds::from_two(&Point(min_x, min_y), &Point(max_x, max_y));
+        Node::<T> { bounds, content: NodeContent::Items(Vec::new()), parent_id: None, depth: 0 }
+    }
+
+    // Returns false if the item is outside of the bounds of the tree
+    pub fn insert(&mut self, item: &'t T) -> bool {
+        if !self.nodes.get(&Self::ROOT).unwrap().bounds.contains(item.position()) {
+            return false;
+        }
+        self.insert_into(Self::ROOT, item);
+        self.item_count += 1;
+        true
+    }
+
+    fn insert_into(&mut self, node_id: u32, item: &'t T) {
+        let node: &mut Node<T> = self.nodes.get_mut(&node_id).expect("Node should exist");
+        match &mut node.content {
+            NodeContent::Items(items) if items.len() < self.max_fill || node.depth >= MAX_DEPTH => items.push(item),
+            NodeContent::Items(_) => {
+                self.split_node(node_id);
+                self.insert_into(node_id, item);
+            }
+            NodeContent::Children { .. } => {
+                let child_id = node.child_id(Quadrant::of(&node.bounds, item.position()));
+                self.insert_into(child_id, item);
+            }
+        }
+    }
+
+    fn split_node(&mut self, node_id: u32) {
+        let first_child_id = self.next_id;
+        self.next_id += 4;
+
+        let node: Node<T> = self.nodes.remove(&node_id).expect("Node should exist");
+        let Node { parent_id, bounds, depth, .. } = node;
+        let items: Vec<&T> = node.get_items();
+        for (index, (quadrant, child_bounds)) in Quadrant::ALL.iter().zip(Quadrant::split(&bounds)).enumerate() {
+            let child_items: Vec<&T> =
+                items.iter()
+                    .filter(|item| Quadrant::of(&bounds, item.position()) == *quadrant)
+                    .cloned()
+                    .collect();
+            let child = Node::<T> {
+                parent_id: Some(node_id),
+                content: NodeContent::Items(child_items),
+                bounds: child_bounds,
+                depth: depth + 1
+            };
+            self.nodes.insert(first_child_id + index as u32, child);
+        }
+        let updated_node = Node {
+            content: NodeContent::Children::<T> {
+                top_left_id: first_child_id,
+                top_right_id: first_child_id + 1,
+                bottom_right_id: first_child_id + 2,
+                bottom_left_id: first_child_id + 3
+            },
+            bounds,
+            parent_id,
+            depth
+        };
+        self.nodes.insert(node_id, updated_node);
+    }
+
+    // Leaf that an item at the given position belongs to
+    fn leaf_for(&self, position: &Point) -> u32 {
+        let mut node_id = Self::ROOT;
+        loop {
+            let node: &Node<T> = self.nodes.get(&node_id).expect("Node should exist");
+            match &node.content {
+                NodeContent::Items(_) => return node_id,
+                NodeContent::Children { .. } => node_id = node.child_id(Quadrant::of(&node.bounds, position))
+            }
+        }
+    }
+
+    // Checks that every item is stored exactly once, in the leaf its position belongs to, and
+    // that the nodes are linked up consistently
+    pub fn check_invariants(&self) -> Result<(), String> {
+        // Items are told apart by their address, since T might not even be comparable
+        let mut seen: HashMap<*const T, (usize, Point)> = HashMap::new();
+        let mut pending: Vec<u32> = vec![Self::ROOT];
+        let mut reachable_nodes: usize = 0;
+        while let Some(node_id) = pending.pop() {
+            reachable_nodes += 1;
+            let node: &Node<T> = self.nodes.get(&node_id).ok_or(format!("Node {node_id} is missing"))?;
+            match &node.content {
+                NodeContent::Items(items) => {
+                    if items.len() > self.max_fill && node.depth < MAX_DEPTH {
+                        return Err(format!("Node {node_id} holds {} items above the maximum depth", items.len()));
+                    }
+                    for item in items.iter() {
+                        let expected_id = self.leaf_for(item.position());
+                        if expected_id != node_id {
+                            return Err(format!("Item at {:?} is in node {node_id} instead of {expected_id}", item.position()));
+                        }
+                        seen.entry(ptr::from_ref(*item)).or_insert((0, *item.position())).0 += 1;
+                    }
+                }
+                NodeContent::Children { .. } => {
+                    for (quadrant, child_bounds) in Quadrant::ALL.into_iter().zip(Quadrant::split(&node.bounds)) {
+                        let child_id = node.child_id(quadrant);
+                        let child: &Node<T> = self.nodes.get(&child_id).ok_or(format!("Node {child_id} is missing"))?;
+                        if child.parent_id != Some(node_id) || child.depth != node.depth + 1 || child.bounds != child_bounds {
+                            return Err(format!("Node {child_id} doesn't match its parent {node_id}"));
+                        }
+                        pending.push(child_id);
+                    }
+                }
+            }
+        }
+        if let Some((count, position)) = seen.values().find(|(count, _)| *count > 1) {
+            return Err(format!("Item at {position:?} is stored {count} times"));
+        }
+        if seen.len() != self.item_count {
+            return Err(format!("{} items are reachable, but {} were inserted", seen.len(), self.item_count));
+        }
+        if reachable_nodes != self.nodes.len() {
+            return Err(format!("{} nodes are reachable, but the tree has {}", reachable_nodes, self.nodes.len()));
+        }
+        Ok(())
+    }
+
+    pub fn intersect(&self, ray: &Ray, ignore_ray_origin: bool) -> Option<&T> {
+        let root = self.nodes.get(&Self::ROOT).unwrap();
+        let mut queue: BTreeMap<u64, Vec<&Node<T>>> = BTreeMap::from([(0_u64, vec![root])]);
+        let mut best_hit: Option<(u64, &T)> = None;
+
+        while !queue.is_empty() {
+            let (priority, closest_nodes) = queue.pop_first().unwrap();
+            if best_hit.is_some_and(|(best_priority, _)| best_priority < priority) {
+                break;
+            }
+            let hit: Option<(u64, &T)> =
+                closest_nodes.iter()
+                    .filter_map(|node| self.visit_node(ray, node, &mut queue, ignore_ray_origin))
+                    .min_by(|item1, item2| cmp_closest(ray, *item1, *item2))
+                    .map(|hit| {
+                        let hit_priority = to_priority(hit.position().distance(&ray.0));
+                        (hit_priority, hit)
+                    });
+            match (&hit, &best_hit) {
+                (None, _) => {}
+                (Some(_), None) => best_hit = hit,
+                (Some((chp, _)), Some((bhp, _))) => best_hit = if chp < bhp { hit } else { best_hit }
+            }
+        }
+        best_hit.map(|(_, bh)| bh)
+    }
+
+    fn visit_node<'a: 't>(
+        &'a self,
+        ray: &Ray,
+        node: &Node<'t, T>,
+        queue: &mut BTreeMap<u64, Vec<&'a Node<'t, T>>>,
+        ignore_ray_origin: bool) -> Option<&'t T> {
+        match &node.content {
+            NodeContent::Items(items) => self.visit_leaf(ray, items, ignore_ray_origin),
+            NodeContent::Children { top_left_id, top_right_id, bottom_right_id, bottom_left_id } => {
+                self.enqueue_children(ray, &[*top_left_id, *top_right_id, *bottom_left_id, *bottom_right_id], queue);
+                None
+            }
+        }
+    }
+
+    fn visit_leaf(&self, ray: &Ray, items: &Vec<&'t T>, ignore_ray_origin: bool) -> Option<&'t T> {
+        items.iter()
+            .filter(|item| ray.hits(item.position()))
+            .filter(|item| !ignore_ray_origin || item.position().distance(&ray.0) > EPSILON)
+            .min_by(|item1, item2| cmp_closest(ray, **item1, **item2))
+            .copied()
+    }
+
+    fn enqueue_children<'a>(&'a self, ray: &Ray, child_ids: &[u32], queue: &mut BTreeMap<u64, Vec<&'a Node<'t, T>>>) {
+        let children_with_priority =
+            child_ids.iter().filter_map(|child_id| {
+                let child: &Node<'t, T> = self.nodes.get(child_id).unwrap();
+                match ray.intersect_bounds(&child.bounds) {
+                    None => None,
+                    Some(intersection) => {
+                        let priority = to_priority(intersection.distance(&ray.0));
+                        Some((child, priority))
+                    }
+                }
+            });
+        for (child, priority) in children_with_priority {
+            queue.entry(priority).or_default().push(child);
+        }
+    }
+}
+
+impl <'t, T: 't + Positioned2D> SpatialIndex<T> for QuadTree<'t, T> {
+    fn intersect(&self, ray: &Ray, ignore_ray_origin: bool) -> Option<&T> {
+        QuadTree::intersect(self, ray, ignore_ray_origin)
+    }
+}
+
+// General spatial index operations, beyond the ray queries of the beam simulation
+impl <'t, T: 't + Positioned2D> QuadTree<'t, T> {
+    // Empty tree covering the given bounds. Items outside of them can't be inserted
+    pub fn new(bounds: Bounds, max_fill: usize) -> QuadTree<'t, T> {
+        let root = Node::<T> { bounds, content: NodeContent::Items(Vec::new()), parent_id: None, depth: 0 };
+        QuadTree { nodes: HashMap::from([(Self::ROOT, root)]), max_fill, next_id: Self::ROOT + 1, item_count: 0 }
+    }
+
+    // Removes every item equal to the given one. Nodes whose children end up holding few enough
+    // items are turned back into leaves
+    pub fn remove(&mut self, item: &T) -> bool where T: PartialEq {
+        if !self.nodes.get(&Self::ROOT).unwrap().bounds.contains(item.position()) {
+            return false;
+        }
+        let leaf_id = self.leaf_for(item.position());
+        let leaf: &mut Node<T> = self.nodes.get_mut(&leaf_id).unwrap();
+        let parent_id = leaf.parent_id;
+        let removed = match &mut leaf.content {
+            NodeContent::Items(items) => {
+                let before = items.len();
+                items.retain(|other| *other != item);
+                before - items.len()
+            }
+            NodeContent::Children { .. } => panic!("Should have found a leaf")
+        };
+        self.item_count -= removed;
+        let mut ancestor_id = parent_id;
+        while let Some(node_id) = ancestor_id.filter(|_| removed > 0) {
+            if !self.merge_children(node_id) {
+                break;
+            }
+            ancestor_id = self.nodes.get(&node_id).unwrap().parent_id;
+        }
+        removed > 0
+    }
+
+    // Returns whether the children were merged into the node
+    fn merge_children(&mut self, node_id: u32) -> bool {
+        let node: &Node<T> = self.nodes.get(&node_id).expect("Node should exist");
+        let children: [u32; 4] = Quadrant::ALL.map(|quadrant| node.child_id(quadrant));
+        let mut merged: Vec<&'t T> = Vec::new();
+        for child_id in children.iter() {
+            match &self.nodes.get(child_id).expect("Node should exist").content {
+                NodeContent::Children { .. } => return false,
+                NodeContent::Items(items) => merged.extend(items.iter())
+            }
+        }
+        if merged.len() > self.max_fill {
+            return false;
+        }
+        children.iter().for_each(|child_id| { self.nodes.remove(child_id); });
+        self.nodes.get_mut(&node_id).unwrap().content = NodeContent::Items(merged);
+        true
+    }
+
+    pub fn within_bounds(&self, bounds: &Bounds) -> Vec<&'t T> {
+        self.collect_items(|node_bounds| node_bounds.intersects(bounds), |item| bounds.contains(item.position()))
+    }
+
+    pub fn within_radius(&self, centre: &Point, radius: f32) -> Vec<&'t T> {
+        self.collect_items(
+            |node_bounds| node_bounds.distance_to(centre) <= radius,
+            |item| item.position().distance(centre) <= radius)
+    }
+
+    // Up to k items, closest first. Nodes are visited in order of their distance to the target,
+    // and the search stops once the closest unvisited node is farther than the k-th item found
+    pub fn nearest(&self, target: &Point, k: usize) -> Vec<&'t T> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let mut queue: BinaryHeap<PendingNode> = BinaryHeap::from([PendingNode { distance: 0.0, node_id: Self::ROOT }]);
+        let mut found: Vec<&'t T> = Vec::new();
+        while let Some(PendingNode { distance, node_id }) = queue.pop() {
+            let kth_distance = found.get(k - 1).map(|item| item.position().distance(target));
+            if kth_distance.is_some_and(|kth_distance| kth_distance < distance) {
+                break;
+            }
+            match &self.nodes.get(&node_id).unwrap().content {
+                // Quadrants are half-open, so every item is stored in a single leaf
+                NodeContent::Items(items) => found.extend(items.iter()),
+                NodeContent::Children { top_left_id, top_right_id, bottom_right_id, bottom_left_id } => {
+                    for child_id in [*top_left_id, *top_right_id, *bottom_right_id, *bottom_left_id] {
+                        let child_bounds = &self.nodes.get(&child_id).unwrap().bounds;
+                        queue.push(PendingNode { distance: child_bounds.distance_to(target), node_id: child_id });
+                    }
+                }
+            }
+            found.sort_by(|item1, item2| item1.position().distance(target).total_cmp(&item2.position().distance(target)));
+            found.truncate(k);
+        }
+        found
+    }
+
+    pub fn paths(&self) -> ItemPaths<'_, 't, T> {
+        ItemPaths { tree: self, pending: vec![(Self::ROOT, NodePath(Vec::new()))], current: None }
+    }
+
+    // Visits the nodes whose bounds pass the first check, and keeps the items that pass the second
+    fn collect_items<N, I>(&self, visit_node: N, keep_item: I) -> Vec<&'t T>
+    where
+        N: Fn(&Bounds) -> bool,
+        I: Fn(&T) -> bool
+    {
+        let mut result: Vec<&'t T> = Vec::new();
+        let mut pending: Vec<u32> = vec![Self::ROOT];
+        while let Some(node_id) = pending.pop() {
+            let node: &Node<T> = self.nodes.get(&node_id).unwrap();
+            if !visit_node(&node.bounds) {
+                continue;
+            }
+            match &node.content {
+                NodeContent::Items(items) => result.extend(items.iter().filter(|item| keep_item(item))),
+                NodeContent::Children { top_left_id, top_right_id, bottom_right_id, bottom_left_id } =>
+                    pending.extend([*top_left_id, *top_right_id, *bottom_right_id, *bottom_left_id])
+            }
+        }
+        result
+    }
+}
+
+// Node waiting in the nearest neighbour search. The heap pops the closest node first
+struct PendingNode {
+    distance: f32,
+    node_id: u32
+}
+
+impl PartialEq for PendingNode {
+    fn eq(&self, other: &PendingNode) -> bool { self.cmp(other) == Ordering::Equal }
+}
+
+impl Eq for PendingNode {}
+
+impl PartialOrd for PendingNode {
+    fn partial_cmp(&self, other: &PendingNode) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for PendingNode {
+    fn cmp(&self, other: &PendingNode) -> Ordering {
+        other.distance.total_cmp(&self.distance).then(other.node_id.cmp(&self.node_id))
+    }
+}
+
+impl <'q, 't, T: Positioned2D> Iterator for ItemPaths<'q, 't, T> {
+    type Item = (NodePath, &'t T);
+
+    fn next(&mut self) -> Option<(NodePath, &'t T)> {
+        loop {
+            if let Some((path, items)) = &mut self.current {
+                if let Some(item) = items.next() {
+                    return Some((path.clone(), *item));
+                }
+                self.current = None;
+            }
+            let (node_id, path) = self.pending.pop()?;
+            match &self.tree.nodes.get(&node_id).unwrap().content {
+                NodeContent::Items(items) => self.current = Some((path, items.iter())),
+                NodeContent::Children { top_left_id, top_right_id, bottom_right_id, bottom_left_id } => {
+                    // Pushed in reverse so that the top left quadrant is visited first
+                    let children = [
+                        (*bottom_left_id, Quadrant::BottomLeft),
+                        (*bottom_right_id, Quadrant::BottomRight),
+                        (*top_right_id, Quadrant::TopRight),
+                        (*top_left_id, Quadrant::TopLeft)
+                    ];
+                    for (child_id, quadrant) in children {
+                        let mut child_path = path.clone();
+                        child_path.0.push(quadrant);
+                        self.pending.push((child_id, child_path));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for NodePath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Root")?;
+        for quadrant in self.0.iter() {
+            let name = match quadrant {
+                Quadrant::TopLeft => "TL",
+                Quadrant::TopRight => "TR",
+                Quadrant::BottomRight => "BR",
+                Quadrant::BottomLeft => "BL"
+            };
+            write!(f, "/{name}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Item(Point);
+
+    impl Positioned2D for Item {
+        fn position(&self) -> &Point { &self.0 }
+    }
+
+    // Points spread over [0, 100) x [0, 100), the same ones on every run
+    fn scattered_items(count: usize) -> Vec<Item> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            ((state >> 33) % 10_000) as f32 / 100.0
+        };
+        (0..count).map(|_| Item(Point(next(), next()))).collect()
+    }
+
+    fn area() -> Bounds { Bounds::from_two(&Point(0.0, 0.0), &Point(100.0, 100.0)) }
+
+    fn sorted_positions(items: &[&Item]) -> Vec<(f32, f32)> {
+        let mut positions: Vec<(f32, f32)> = items.iter().map(|Item(Point(x, y))| (*x, *y)).collect();
+        positions.sort_by(|p1, p2| p1.0.total_cmp(&p2.0).then(p1.1.total_cmp(&p2.1)));
+        positions
+    }
+
+    #[test]
+    fn removing_items_merges_the_nodes_back() {
+        let items = scattered_items(200);
+        let mut tree: QuadTree<Item> = QuadTree::new(area(), 4);
+        items.iter().for_each(|item| assert!(tree.insert(item)));
+        assert!(tree.nodes.len() > 1);
+
+        for item in items[3..].iter() {
+            assert!(tree.remove(item));
+        }
+        assert_eq!(tree.nodes.len(), 1);
+        assert_eq!(sorted_positions(&tree.within_bounds(&area())), sorted_positions(&items[..3].iter().collect::<Vec<&Item>>()));
+        assert!(!tree.remove(&items[10]));
+    }
+
+    #[test]
+    fn range_queries_match_a_scan() {
+        let items = scattered_items(300);
+        let tree: QuadTree<Item> = QuadTree::from_bulk(&items, 4);
+        let queries = [
+            Bounds::from_two(&Point(10.0, 10.0), &Point(30.0, 45.0)),
+            Bounds::from_two(&Point(50.0, 0.0), &Point(50.5, 100.0)),
+            Bounds::from_two(&Point(-5.0, -5.0), &Point(200.0, 200.0)),
+            Bounds::from_two(&Point(99.0, 99.0), &Point(120.0, 120.0))
+        ];
+        for bounds in queries {
+            let expected: Vec<&Item> = items.iter().filter(|item| bounds.contains(item.position())).collect();
+            assert_eq!(sorted_positions(&tree.within_bounds(&bounds)), sorted_positions(&expected), "{bounds:?}");
+        }
+    }
+
+    #[test]
+    fn radius_queries_match_a_scan() {
+        let items = scattered_items(300);
+        let tree: QuadTree<Item> = QuadTree::from_bulk(&items, 4);
+        for (centre, radius) in [(Point(50.0, 50.0), 10.0), (Point(0.0, 100.0), 30.0), (Point(75.5, 20.25), 0.5), (Point(-50.0, 50.0), 200.0)] {
+            let expected: Vec<&Item> = items.iter().filter(|item| item.position().distance(&centre) <= radius).collect();
+            assert_eq!(sorted_positions(&tree.within_radius(&centre, radius)), sorted_positions(&expected), "{centre:?} {radius}");
+        }
+    }
+
+    #[test]
+    fn nearest_items_match_a_scan() {
+        let items = scattered_items(300);
+        let tree: QuadTree<Item> = QuadTree::from_bulk(&items, 4);
+        for target in scattered_items(50).iter().map(|item| item.0).chain([Point(-20.0, 130.0)]) {
+            let mut expected: Vec<f32> = items.iter().map(|item| item.position().distance(&target)).collect();
+            expected.sort_by(f32::total_cmp);
+            for k in [0, 1, 2, 5, 17] {
+                let found: Vec<f32> = tree.nearest(&target, k).iter().map(|item| item.position().distance(&target)).collect();
+                assert_eq!(found, expected[..k], "{target:?} {k}");
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_tells_apart_close_distances() {
+        // Both distances round to the same thousandth
+        let items = [Item(Point(1.00004, 0.0)), Item(Point(0.0, -1.00001)), Item(Point(5.0, 5.0)), Item(Point(-5.0, 5.0))];
+        let mut tree: QuadTree<Item> = QuadTree::new(Bounds::from_two(&Point(-8.0, -8.0), &Point(8.0, 8.0)), 1);
+        items.iter().for_each(|item| assert!(tree.insert(item)));
+        assert_eq!(tree.nearest(&Point::ORIGIN, 1), vec![&items[1]]);
+        assert_eq!(tree.nearest(&Point::ORIGIN, 2), vec![&items[1], &items[0]]);
+    }
+
+    #[test]
+    fn paths_lead_to_every_item_once() {
+        let items = scattered_items(50);
+        let tree: QuadTree<Item> = QuadTree::from_bulk(&items, 2);
+        let paths: Vec<(NodePath, &Item)> = tree.paths().collect();
+        assert_eq!(paths.len(), items.len());
+        for (path, item) in paths {
+            let mut node_id = QuadTree::<Item>::ROOT;
+            for quadrant in path.0.iter() {
+                node_id = tree.nodes[&node_id].child_id(*quadrant);
+            }
+            assert_eq!(node_id, tree.leaf_for(item.position()), "{path}");
+        }
+        assert_eq!(NodePath(vec![Quadrant::TopLeft, Quadrant::BottomRight]).to_string(), "Root/TL/BR");
+    }
+
+    fn deepest_leaf(tree: &QuadTree<Item>) -> u32 {
+        tree.nodes.values()
+            .filter(|node| matches!(node.content, NodeContent::Items(_)))
+            .map(|node| node.depth)
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn coincident_items_stop_at_the_maximum_depth() {
+        let items: Vec<Item> = (0..10).map(|_| Item(Point(3.0, 3.0))).chain([Item(Point(1.0, 7.0))]).collect();
+        let mut tree: QuadTree<Item> = QuadTree::new(Bounds::from_two(&Point(0.0, 0.0), &Point(8.0, 8.0)), 2);
+        for item in items.iter() {
+            assert!(tree.insert(item));
+            tree.check_invariants().unwrap();
+        }
+        assert_eq!(deepest_leaf(&tree), MAX_DEPTH);
+        assert_eq!(tree.within_radius(&Point(3.0, 3.0), 0.0).len(), 10);
+    }
+
+    #[test]
+    fn items_on_split_lines_are_stored_once() {
+        let items: Vec<Item> = (0..=8).flat_map(|x| (0..=8).map(move |y| Item(Point(x as f32, y as f32)))).collect();
+        let mut tree: QuadTree<Item> = QuadTree::new(Bounds::from_two(&Point(0.0, 0.0), &Point(8.0, 8.0)), 1);
+        for item in items.iter() {
+            assert!(tree.insert(item));
+            tree.check_invariants().unwrap();
+        }
+        assert_eq!(tree.within_bounds(&area()).len(), items.len());
+        assert_eq!(tree.paths().count(), items.len());
+    }
+
+    #[test]
+    fn interleaved_inserts_and_removals_keep_the_tree_consistent() {
+        let items: Vec<Item> =
+            scattered_items(120).into_iter()
+                .chain((0..6).map(|_| Item(Point(50.0, 50.0))))
+                .collect();
+        let mut tree: QuadTree<Item> = QuadTree::new(area(), 3);
+        let mut live: Vec<&Item> = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            assert!(tree.insert(item));
+            live.push(item);
+            tree.check_invariants().unwrap();
+            if index % 3 == 2 {
+                let removed = &items[index / 2];
+                assert_eq!(tree.remove(removed), live.contains(&removed));
+                live.retain(|other| *other != removed);
+                tree.check_invariants().unwrap();
+            }
+        }
+        assert_eq!(sorted_positions(&tree.within_bounds(&area())), sorted_positions(&live));
+        for item in items.iter() {
+            tree.remove(item);
+            tree.check_invariants().unwrap();
+        }
+        assert_eq!(tree.nodes.len(), 1);
+    }
+}
@@ -0,0 +1,13 @@
+use std::cmp::Ordering;
+
+use crate::geometry::Ray;
+use super::Positioned2D;
+
+// Orders the items by distance to the origin of the ray, for the ray searches of both quadtrees
+pub fn cmp_closest<T: Positioned2D>(ray: &Ray, item1: &T, item2: &T) -> Ordering {
+    let distance1: f32 = item1.position().distance(&ray.0);
+    let distance2: f32 = item2.position().distance(&ray.0);
+    distance1.partial_cmp(&distance2).unwrap()
+}
+
+pub fn to_priority(value: f32) -> u64 { ((value * 10.0_f32.powi(3)).round() * 10.0) as u64 }